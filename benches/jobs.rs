@@ -0,0 +1,68 @@
+//! Measures the cost of updating jobs when many are running at once, which
+//! depends on how quickly the output state can find each job's log.
+//!
+//! Run with `cargo bench --bench jobs`.
+
+extern crate output;
+
+use std::time::Instant;
+
+use output::*;
+
+const UPDATES: u64 = 1_000_000;
+
+struct NullBackend;
+
+impl Backend for NullBackend {
+
+	fn update (
+		& mut self,
+		_logs: & [OutputLogInternal],
+	) {
+	}
+
+	fn synchronous (& self) -> bool {
+		false
+	}
+
+}
+
+fn main () {
+
+	for & job_count in & [ 10, 100, 1000, 10000 ] {
+
+		let output =
+			Output::new (Some (Box::new (NullBackend)));
+
+		let jobs: Vec <OutputLog> =
+			(0 .. job_count).map (
+				|index| output.start_job (format! ("job {}", index)),
+			).collect ();
+
+		let start = Instant::now ();
+
+		for index in 0 .. UPDATES {
+
+			jobs [(index % job_count) as usize].progress (
+				index,
+				UPDATES);
+
+		}
+
+		let elapsed = start.elapsed ();
+
+		println! (
+			"{:>6} jobs {:>10.1} ns/update",
+			job_count,
+			(elapsed.as_secs () as f64 * 1e9 + elapsed.subsec_nanos () as f64)
+				/ UPDATES as f64);
+
+		for job in jobs {
+			job.complete ();
+		}
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
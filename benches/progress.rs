@@ -0,0 +1,86 @@
+//! Compares the cost of reporting progress through `OutputLog`, which takes
+//! the output lock on every call, with `ProgressHandle`, which does not.
+//!
+//! Run with `cargo bench --bench progress`.
+
+extern crate output;
+
+use std::time::Duration;
+use std::time::Instant;
+
+use output::*;
+
+const ITERATIONS: u64 = 1_000_000;
+
+struct NullBackend;
+
+impl Backend for NullBackend {
+
+	fn update (
+		& mut self,
+		_logs: & [OutputLogInternal],
+	) {
+	}
+
+	fn synchronous (& self) -> bool {
+		false
+	}
+
+}
+
+fn measure <Body: FnMut (u64)> (
+	name: & str,
+	mut body: Body,
+) {
+
+	let start = Instant::now ();
+
+	for index in 0 .. ITERATIONS {
+		body (index);
+	}
+
+	let elapsed = start.elapsed ();
+
+	println! (
+		"{:<24} {:>8.1} ns/iter",
+		name,
+		nanos (elapsed) / ITERATIONS as f64);
+
+}
+
+fn nanos (
+	duration: Duration,
+) -> f64 {
+
+	duration.as_secs () as f64 * 1e9 + duration.subsec_nanos () as f64
+
+}
+
+fn main () {
+
+	let output =
+		Output::new (Some (Box::new (NullBackend)));
+
+	let job =
+		output.start_job ("benchmark");
+
+	measure ("OutputLog::progress", |index|
+		job.progress (index, ITERATIONS));
+
+	measure ("OutputLog::tick", |_index|
+		job.tick ());
+
+	let progress_handle =
+		job.progress_handle ();
+
+	measure ("ProgressHandle::progress", |index|
+		progress_handle.progress (index));
+
+	measure ("ProgressHandle::tick", |_index|
+		progress_handle.tick ());
+
+	job.complete ();
+
+}
+
+// ex: noet ts=4 filetype=rust
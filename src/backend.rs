@@ -1,4 +1,5 @@
 use output_log::*;
+use summary::*;
 
 pub type BoxBackend = Box <Backend>;
 
@@ -11,6 +12,14 @@ pub trait Backend: Send {
 
 	fn synchronous (& self) -> bool;
 
+	/// Called before each update with progress across all jobs, if the
+	/// header showing it is enabled, or `None` if not.
+	fn summary (
+		& mut self,
+		_summary: Option <& OutputSummary>,
+	) {
+	}
+
 }
 
 // ex: noet ts=4 filetype=rust
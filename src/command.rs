@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::process::Command;
+use std::process::ExitStatus;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::thread;
+
+use output::*;
+use remote::*;
+
+impl Output {
+
+	/// Run a command as a job, showing each line it writes to stdout or
+	/// stderr as a message. The job completes or is marked incomplete
+	/// according to the exit status. Stdout and stderr are replaced with
+	/// pipes, but stdin is left as set on the command.
+	#[ track_caller ]
+	pub fn run_command <
+		Label: Into <String>,
+	> (
+		& self,
+		command: & mut Command,
+		label: Label,
+	) -> io::Result <ExitStatus> {
+
+		self.run_command_real (
+			command,
+			label.into (),
+			None,
+			false)
+
+	}
+
+	/// Run a command as a job like `run_command`, and also let it report its
+	/// own jobs, if it uses this crate, which are shown nested under this
+	/// one. See `RemoteListener`.
+	#[ track_caller ]
+	pub fn run_command_remote <
+		Label: Into <String>,
+	> (
+		& self,
+		command: & mut Command,
+		label: Label,
+	) -> io::Result <ExitStatus> {
+
+		self.run_command_real (
+			command,
+			label.into (),
+			None,
+			true)
+
+	}
+
+	/// Run a command as a job, showing only its latest line of output while
+	/// it runs. If it fails, the last `tail_lines` lines are shown along
+	/// with the exit status.
+	#[ track_caller ]
+	pub fn run_command_collapsed <
+		Label: Into <String>,
+	> (
+		& self,
+		command: & mut Command,
+		label: Label,
+		tail_lines: usize,
+	) -> io::Result <ExitStatus> {
+
+		self.run_command_real (
+			command,
+			label.into (),
+			Some (tail_lines),
+			false)
+
+	}
+
+	#[ track_caller ]
+	fn run_command_real (
+		& self,
+		command: & mut Command,
+		label: String,
+		tail_lines: Option <usize>,
+		remote: bool,
+	) -> io::Result <ExitStatus> {
+
+		let output_log =
+			self.start_job (
+				label);
+
+		// child processes using this crate report their jobs back to us,
+		// nested under this one
+
+		let remote_listener =
+			if remote {
+
+				match RemoteListener::new (
+					& self.prefix ("  ".to_string ())) {
+
+					Ok (remote_listener) => Some (remote_listener),
+
+					Err (error) => {
+						output_log.incomplete_with (& error);
+						return Err (error);
+					},
+
+				}
+
+			} else {
+				None
+			};
+
+		if let Some (ref remote_listener) = remote_listener {
+			remote_listener.apply (command);
+		}
+
+		let mut child =
+			match command
+				.stdout (Stdio::piped ())
+				.stderr (Stdio::piped ())
+				.spawn () {
+
+			Ok (child) => child,
+
+			Err (error) => {
+				output_log.incomplete_with (& error);
+				return Err (error);
+			},
+
+		};
+
+		// read both streams on their own threads, so neither can block the
+		// child, and collect the lines here
+
+		let (line_sender, line_receiver) =
+			mpsc::channel ();
+
+		let mut reader_threads = Vec::new ();
+
+		if let Some (stdout) = child.stdout.take () {
+
+			reader_threads.push (
+				spawn_reader (
+					stdout,
+					line_sender.clone ()));
+
+		}
+
+		if let Some (stderr) = child.stderr.take () {
+
+			reader_threads.push (
+				spawn_reader (
+					stderr,
+					line_sender.clone ()));
+
+		}
+
+		drop (line_sender);
+
+		let mut tail =
+			VecDeque::new ();
+
+		for line in line_receiver {
+
+			if let Some (tail_lines) = tail_lines {
+
+				output_log.detail (
+					line.clone ());
+
+				if tail_lines > 0 {
+
+					if tail.len () == tail_lines {
+						tail.pop_front ();
+					}
+
+					tail.push_back (
+						line);
+
+				}
+
+			} else {
+
+				self.message (
+					line);
+
+			}
+
+		}
+
+		for reader_thread in reader_threads {
+			reader_thread.join ().expect (
+				"Output::run_command");
+		}
+
+		let status =
+			match child.wait () {
+
+			Ok (status) => status,
+
+			Err (error) => {
+				output_log.incomplete_with (& error);
+				return Err (error);
+			},
+
+		};
+
+		drop (remote_listener);
+
+		if status.success () {
+
+			output_log.complete ();
+
+		} else {
+
+			let mut lines =
+				vec! [ status.to_string () ];
+
+			lines.extend (
+				tail);
+
+			output_log.fail_with_lines (
+				lines);
+
+		}
+
+		Ok (status)
+
+	}
+
+}
+
+fn spawn_reader <
+	Source: Read + Send + 'static,
+> (
+	source: Source,
+	line_sender: mpsc::Sender <String>,
+) -> thread::JoinHandle <()> {
+
+	thread::spawn (move || {
+
+		for line in BufReader::new (source).split (b'\n') {
+
+			let line = match line {
+				Ok (line) => line,
+				Err (_) => break,
+			};
+
+			let line =
+				String::from_utf8_lossy (
+					& line,
+				).trim_end_matches ('\r').to_string ();
+
+			if line_sender.send (line).is_err () {
+				break;
+			}
+
+		}
+
+	})
+
+}
+
+// ex: noet ts=4 filetype=rust
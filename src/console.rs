@@ -3,15 +3,22 @@ use std::io;
 use std::io::Write;
 
 use termion;
+use termion::color;
 
 use backend::*;
 use output_log::*;
+use summary::*;
+
+/// Lines left free when limiting status lines to the terminal's height.
+pub const STATUS_LINES_MARGIN: u16 = 2;
 
 pub struct Console <'a> {
 	status_tick_sequence: & 'a [String],
 	error_handler: Box <Fn (io::Error) + Send>,
 	columns: u16,
+	max_status_lines: u16,
 	status_lines: u16,
+	summary: Option <String>,
 }
 
 impl <'a> Console <'a> {
@@ -21,13 +28,13 @@ impl <'a> Console <'a> {
 		status_tick_sequence: & 'a [String],
 	) -> Console <'a> {
 
-		let columns =
+		let (columns, rows) =
 			match termion::terminal_size () {
 
-			Ok ((columns, _rows)) =>
-				columns,
+			Ok ((columns, rows)) =>
+				(columns, rows),
 
-			Err (_) => 80,
+			Err (_) => (80, 24),
 
 		};
 
@@ -35,11 +42,26 @@ impl <'a> Console <'a> {
 			status_tick_sequence: status_tick_sequence,
 			error_handler: error_handler,
 			columns: columns,
+			max_status_lines: rows.saturating_sub (STATUS_LINES_MARGIN),
 			status_lines: 0,
+			summary: None,
 		}
 
 	}
 
+	/// Limit the number of lines used to show running jobs. Jobs which do
+	/// not fit are summarised on the last line. Defaults to the terminal's
+	/// height, less `STATUS_LINES_MARGIN`.
+	pub fn set_max_status_lines (
+		& mut self,
+		max_status_lines: u16,
+	) {
+
+		self.max_status_lines =
+			max_status_lines;
+
+	}
+
 	fn write_message (
 		& self,
 		target: & mut FormatWrite,
@@ -55,44 +77,6 @@ impl <'a> Console <'a> {
 
 	}
 
-	fn write_running (
-		& self,
-		target: & mut FormatWrite,
-		message: & str,
-		status: Option <& str>,
-	) {
-
-		if let Some (status) = status {
-
-			write! (
-				target,
-				"{} ... {}{}\r\n",
-				if message.len () <= self.columns as usize - status.len () - 5 {
-					& message
-				} else {
-					& message [0 .. self.columns as usize - status.len () - 5]
-				},
-				status,
-				termion::clear::AfterCursor,
-			).unwrap ();
-
-		} else {
-
-			write! (
-				target,
-				"{} ...{}\r\n",
-				if message.len () <= self.columns as usize - 4 {
-					& message
-				} else {
-					& message [0 .. self.columns as usize - 4]
-				},
-				termion::clear::AfterCursor,
-			).unwrap ();
-
-		}
-
-	}
-
 }
 
 impl <'a> Backend for Console <'a> {
@@ -118,7 +102,7 @@ impl <'a> Backend for Console <'a> {
 
 		}
 
-		// output logs
+		// output permanent logs, which includes finished jobs
 
 		let old_status_lines = self.status_lines;
 		self.status_lines = 0;
@@ -133,77 +117,85 @@ impl <'a> Backend for Console <'a> {
 
 			} else if log.state () == OutputLogState::Complete {
 
-				self.write_running (
+				write_finished (
+					& mut buffer,
+					self.columns,
+					log);
+
+			} else if log.state () == OutputLogState::Incomplete {
+
+				write_finished (
+					& mut buffer,
+					self.columns,
+					log);
+
+				write_failure (
 					& mut buffer,
-					log.message (),
-					Some ("done"));
+					self.columns,
+					log);
 
 			}
 
 		}
 
-		for log in logs {
+		// output the summary header, then running jobs, as many as will fit
 
-			if log.state () == OutputLogState::Removed
-			|| log.state () == OutputLogState::Message
-			|| log.state () == OutputLogState::Complete {
-				continue;
-			}
+		let mut max_status_lines =
+			self.max_status_lines;
 
-			if log.state () == OutputLogState::Running
-				|| self.status_lines > 0 {
+		// the summary is only shown if there is room for it
 
-				self.status_lines += 1;
+		if let Some (ref summary) = self.summary {
 
-			}
+			if max_status_lines > 0 {
 
-			if log.state () == OutputLogState::Running {
+				write_summary (
+					& mut buffer,
+					self.columns,
+					summary);
 
-				if log.denominator () > 0 {
+				self.status_lines += 1;
 
-					let percent_string =
-						format! (
-							"{}%",
-							log.numerator () * 100 / log.denominator ());
+				max_status_lines -= 1;
 
-					self.write_running (
-						& mut buffer,
-						log.message (),
-						Some (& percent_string));
+			}
 
-				} else if log.tick () > 0 {
+		}
 
-					let tick_string =
-						& self.status_tick_sequence [
-							(log.tick () as usize - 1)
-								% self.status_tick_sequence.len ()];
+		let running: Vec <& OutputLogInternal> =
+			logs.iter ().filter (
+				|log| log.state () == OutputLogState::Running
+			).collect ();
 
-					self.write_running (
-						& mut buffer,
-						log.message (),
-						Some (& tick_string));
+		let (log_lines, hidden) =
+			allocate_status_lines (
+				& running,
+				max_status_lines);
 
-				} else {
+		for (log, & lines) in running.iter ().zip (log_lines.iter ()) {
 
-					self.write_running (
-						& mut buffer,
-						log.message (),
-						None);
+			if lines == 0 {
+				continue;
+			}
 
-				}
+			write_status (
+				& mut buffer,
+				self.columns,
+				self.status_tick_sequence,
+				log,
+				lines);
 
-			} else if log.state () == OutputLogState::Incomplete {
+			self.status_lines += lines;
 
-				self.write_running (
-					& mut buffer,
-					log.message (),
-					Some ("abort"));
+		}
 
-			} else {
+		if hidden > 0 {
 
-				unreachable! ();
+			write_overflow (
+				& mut buffer,
+				hidden);
 
-			}
+			self.status_lines += 1;
 
 		}
 
@@ -242,10 +234,453 @@ impl <'a> Backend for Console <'a> {
 
 	}
 
+	fn summary (
+		& mut self,
+		summary: Option <& OutputSummary>,
+	) {
+
+		// only shown while there are jobs running
+
+		self.summary =
+			summary.filter (
+				|summary| summary.running () > 0,
+			).map (
+				|summary| summary.to_string ());
+
+	}
+
 	fn synchronous (& self) -> bool {
 		false
 	}
 
 }
 
+pub (crate) fn write_running (
+	target: & mut FormatWrite,
+	columns: u16,
+	message: & str,
+	status: Option <& str>,
+) {
+
+	if let Some (status) = status {
+
+		write! (
+			target,
+			"{} ... {}{}\r\n",
+			truncate (
+				message,
+				(columns as usize).saturating_sub (status.chars ().count () + 5)),
+			status,
+			termion::clear::AfterCursor,
+		).unwrap ();
+
+	} else {
+
+		write! (
+			target,
+			"{} ...{}\r\n",
+			truncate (
+				message,
+				(columns as usize).saturating_sub (4)),
+			termion::clear::AfterCursor,
+		).unwrap ();
+
+	}
+
+}
+
+pub (crate) fn write_status (
+	target: & mut FormatWrite,
+	columns: u16,
+	status_tick_sequence: & [String],
+	log: & OutputLogInternal,
+	lines: u16,
+) {
+
+	if let Some (progress_string) = log.progress_string () {
+
+		write_running (
+			target,
+			columns,
+			log.message (),
+			Some (& progress_string));
+
+	} else if let Some (spinner_frame) = log.spinner_frame () {
+
+		write_running (
+			target,
+			columns,
+			log.message (),
+			Some (spinner_frame));
+
+	} else if log.tick () > 0 {
+
+		let tick_string =
+			& status_tick_sequence [
+				(log.tick () as usize - 1)
+					% status_tick_sequence.len ()];
+
+		write_running (
+			target,
+			columns,
+			log.message (),
+			Some (& tick_string));
+
+	} else {
+
+		write_running (
+			target,
+			columns,
+			log.message (),
+			None);
+
+	}
+
+	let mut extra_lines =
+		lines as usize - 1;
+
+	if extra_lines == 0 {
+		return;
+	}
+
+	if let Some (detail) = log.detail () {
+
+		write_detail (
+			target,
+			columns,
+			detail);
+
+		extra_lines -= 1;
+
+	}
+
+	// show the latest captured lines, if they don't all fit
+
+	let group_lines =
+		log.group_lines ();
+
+	write_group_lines (
+		target,
+		columns,
+		& group_lines [
+			group_lines.len () - extra_lines.min (group_lines.len ()) ..]);
+
+}
+
+pub (crate) fn write_summary (
+	target: & mut FormatWrite,
+	columns: u16,
+	summary: & str,
+) {
+
+	let summary: String =
+		summary.chars ().take (
+			columns as usize,
+		).collect ();
+
+	write! (
+		target,
+		"{}{}{}{}\r\n",
+		termion::style::Bold,
+		summary,
+		termion::style::Reset,
+		termion::clear::AfterCursor,
+	).unwrap ();
+
+}
+
+pub (crate) fn write_overflow (
+	target: & mut FormatWrite,
+	hidden: usize,
+) {
+
+	write! (
+		target,
+		"{}... and {} more running{}{}\r\n",
+		termion::style::Faint,
+		hidden,
+		termion::style::Reset,
+		termion::clear::AfterCursor,
+	).unwrap ();
+
+}
+
+pub (crate) fn write_finished (
+	target: & mut FormatWrite,
+	columns: u16,
+	log: & OutputLogInternal,
+) {
+
+	let status =
+		log.status_string ();
+
+	let message =
+		log.message ();
+
+	write! (
+		target,
+		"{} ... {}{}{}{}\r\n",
+		truncate (
+			message,
+			(columns as usize).saturating_sub (status.chars ().count () + 5)),
+		status_colour (log.status_style ()),
+		status,
+		color::Fg (color::Reset),
+		termion::clear::AfterCursor,
+	).unwrap ();
+
+}
+
+pub (crate) fn write_detail (
+	target: & mut FormatWrite,
+	columns: u16,
+	detail: & str,
+) {
+
+	let detail: String =
+		detail.chars ().take (
+			(columns as usize).saturating_sub (4),
+		).collect ();
+
+	write! (
+		target,
+		"    {}{}{}{}\r\n",
+		termion::style::Faint,
+		detail,
+		termion::style::Reset,
+		termion::clear::AfterCursor,
+	).unwrap ();
+
+}
+
+fn write_group_lines (
+	target: & mut FormatWrite,
+	columns: u16,
+	group_lines: & [String],
+) {
+
+	for line in group_lines {
+
+		let line: String =
+			line.chars ().take (
+				(columns as usize).saturating_sub (4),
+			).collect ();
+
+		write! (
+			target,
+			"    {}{}{}{}\r\n",
+			termion::style::Faint,
+			line,
+			termion::style::Reset,
+			termion::clear::AfterCursor,
+		).unwrap ();
+
+	}
+
+}
+
+/// Write anything a failed group kept, and the reason for failure.
+pub (crate) fn write_failure (
+	target: & mut FormatWrite,
+	columns: u16,
+	log: & OutputLogInternal,
+) {
+
+	for line in log.kept_lines () {
+
+		let line: String =
+			line.chars ().take (
+				(columns as usize).saturating_sub (4),
+			).collect ();
+
+		write! (
+			target,
+			"    {}{}\r\n",
+			line,
+			termion::clear::AfterCursor,
+		).unwrap ();
+
+	}
+
+}
+
+/// Decide how many lines to show for each running job, within the maximum,
+/// giving priority to the most recent. Returns the lines for each job, zero
+/// for those which are hidden, and the number hidden, which are summarised
+/// on a line of their own if this is not zero. With no room at all, nothing
+/// is shown, not even that summary.
+pub (crate) fn allocate_status_lines (
+	running: & [& OutputLogInternal],
+	max_status_lines: u16,
+) -> (Vec <u16>, usize) {
+
+	let wanted: Vec <usize> =
+		running.iter ().map (
+			|log|
+			1 + log.detail ().map (|_| 1).unwrap_or (0)
+				+ log.group_lines ().len ()
+		).collect ();
+
+	if wanted.iter ().sum::<usize> () <= max_status_lines as usize {
+
+		return (
+			wanted.into_iter ().map (|lines| lines as u16).collect (),
+			0,
+		);
+
+	}
+
+	if max_status_lines == 0 {
+
+		return (
+			vec! [0; running.len ()],
+			0,
+		);
+
+	}
+
+	// keep a line back for the summary of those which don't fit
+
+	let mut remaining =
+		max_status_lines as usize - 1;
+
+	let mut lines =
+		vec! [0; running.len ()];
+
+	let mut hidden = 0;
+
+	for index in (0 .. running.len ()).rev () {
+
+		if remaining == 0 {
+			hidden = index + 1;
+			break;
+		}
+
+		lines [index] =
+			wanted [index].min (remaining) as u16;
+
+		remaining -= lines [index] as usize;
+
+	}
+
+	(lines, hidden)
+
+}
+
+/// Truncate to at most the given number of characters, which may be less
+/// than the number of bytes.
+fn truncate (
+	text: & str,
+	width: usize,
+) -> String {
+
+	text.chars ().take (
+		width,
+	).collect ()
+
+}
+
+fn status_colour (
+	style: OutputStatusStyle,
+) -> color::Fg <& 'static color::Color> {
+
+	match style {
+		OutputStatusStyle::Success => color::Fg (& color::Green),
+		OutputStatusStyle::Neutral => color::Fg (& color::Cyan),
+		OutputStatusStyle::Warning => color::Fg (& color::Yellow),
+		OutputStatusStyle::Failure => color::Fg (& color::Red),
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::panic::Location;
+
+	use super::*;
+
+	/// Running logs wanting one, two and three lines, the extra lines being
+	/// captured by groups.
+	fn running_logs (
+	) -> Vec <OutputLogInternal> {
+
+		(0 .. 3).map (
+			|log_id| {
+
+			let mut log =
+				OutputLogInternal::new (
+					log_id,
+					format! ("job {}", log_id),
+					OutputLogState::Running,
+					OutputSeverity::Info,
+					Location::caller ());
+
+			for _ in 0 .. log_id {
+				log.add_group_line ("captured");
+			}
+
+			log
+
+		}).collect ()
+
+	}
+
+	/// Allocate lines, and check the total, including the line for those
+	/// hidden, is within the maximum.
+	fn allocate (
+		max_status_lines: u16,
+	) -> (Vec <u16>, usize) {
+
+		let logs =
+			running_logs ();
+
+		let running: Vec <& OutputLogInternal> =
+			logs.iter ().collect ();
+
+		let (lines, hidden) =
+			allocate_status_lines (
+				& running,
+				max_status_lines);
+
+		let total =
+			lines.iter ().sum::<u16> ()
+				+ if hidden > 0 { 1 } else { 0 };
+
+		assert! (total <= max_status_lines);
+
+		(lines, hidden)
+
+	}
+
+	#[ test ]
+	fn allocate_fits () {
+
+		assert_eq! (allocate (6), (vec! [1, 2, 3], 0));
+		assert_eq! (allocate (20), (vec! [1, 2, 3], 0));
+
+	}
+
+	#[ test ]
+	fn allocate_overflow () {
+
+		// the most recent jobs are shown first, older ones only partly if
+		// that is all that fits, and a line is kept for those which don't
+
+		assert_eq! (allocate (5), (vec! [0, 1, 3], 1));
+		assert_eq! (allocate (4), (vec! [0, 0, 3], 2));
+		assert_eq! (allocate (2), (vec! [0, 0, 1], 2));
+		assert_eq! (allocate (1), (vec! [0, 0, 0], 3));
+
+	}
+
+	#[ test ]
+	fn allocate_no_room () {
+
+		assert_eq! (allocate (0), (vec! [0, 0, 0], 0));
+
+	}
+
+}
+
 // ex: noet ts=4 filetype=rust
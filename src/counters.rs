@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// Progress values for a log, shared so that they can be updated without
+/// taking the output state lock. They are sampled whenever the backend is
+/// updated, and by the background thread when it redraws.
+#[ derive (Default) ]
+pub struct OutputLogCounters {
+	pub numerator: AtomicU64,
+	pub count: AtomicU64,
+	pub counting: AtomicBool,
+	pub tick: AtomicU64,
+}
+
+/// Cloneable handle for updating a job's progress from hot loops or other
+/// threads without taking the output lock. Changes are displayed the next
+/// time the backend is updated. Asynchronous backends are redrawn regularly
+/// by the background thread, but synchronous ones are only updated when
+/// something else happens, such as a message or another job finishing.
+///
+/// The denominator is not shared, and should be set with
+/// `OutputLog::progress` before handing these out.
+#[ derive (Clone) ]
+pub struct ProgressHandle {
+	counters: Arc <OutputLogCounters>,
+}
+
+impl ProgressHandle {
+
+	#[ inline ]
+	pub (crate) fn new (
+		counters: Arc <OutputLogCounters>,
+	) -> ProgressHandle {
+
+		ProgressHandle {
+			counters: counters,
+		}
+
+	}
+
+	#[ inline ]
+	pub fn progress (
+		& self,
+		numerator: u64,
+	) {
+
+		self.counters.numerator.store (
+			numerator,
+			Ordering::Relaxed);
+
+	}
+
+	#[ inline ]
+	pub fn progress_add (
+		& self,
+		delta: u64,
+	) {
+
+		self.counters.numerator.fetch_add (
+			delta,
+			Ordering::Relaxed);
+
+	}
+
+	#[ inline ]
+	pub fn count (
+		& self,
+		count: u64,
+	) {
+
+		self.counters.count.store (
+			count,
+			Ordering::Relaxed);
+
+		self.counters.counting.store (
+			true,
+			Ordering::Relaxed);
+
+	}
+
+	#[ inline ]
+	pub fn count_add (
+		& self,
+		delta: u64,
+	) {
+
+		self.counters.count.fetch_add (
+			delta,
+			Ordering::Relaxed);
+
+		self.counters.counting.store (
+			true,
+			Ordering::Relaxed);
+
+	}
+
+	#[ inline ]
+	pub fn tick (
+		& self,
+	) {
+
+		self.counters.tick.fetch_add (
+			1,
+			Ordering::Relaxed);
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,466 @@
+use std::fmt::Write as FormatWrite;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+use backend::*;
+use output_log::*;
+use timestamp::*;
+
+/// When a `FileBackend` should move the current log file aside.
+#[ derive (Clone, Copy, PartialEq) ]
+pub enum FileRotation {
+	Never,
+	Size (u64),
+	Daily,
+}
+
+/// Backend which appends permanent messages and job outcomes, with
+/// timestamps, to a log file.
+///
+/// Old files are renamed with a numeric suffix, `.1` being the most recent,
+/// and only `keep` of them are retained.
+pub struct FileBackend {
+	error_handler: Box <Fn (io::Error) + Send>,
+	path: PathBuf,
+	rotation: FileRotation,
+	keep: usize,
+	file: Option <File>,
+	size: u64,
+	days: i64,
+}
+
+impl FileBackend {
+
+	pub fn new <
+		Path: Into <PathBuf>,
+	> (
+		error_handler: Box <Fn (io::Error) + Send>,
+		path: Path,
+		rotation: FileRotation,
+		keep: usize,
+	) -> io::Result <FileBackend> {
+
+		let mut file_backend = FileBackend {
+			error_handler: error_handler,
+			path: path.into (),
+			rotation: rotation,
+			keep: keep,
+			file: None,
+			size: 0,
+			days: 0,
+		};
+
+		file_backend.open () ?;
+
+		Ok (file_backend)
+
+	}
+
+	fn open (
+		& mut self,
+	) -> io::Result <()> {
+
+		let file =
+			OpenOptions::new ()
+				.create (true)
+				.append (true)
+				.open (& self.path) ?;
+
+		let metadata =
+			file.metadata () ?;
+
+		self.size = metadata.len ();
+
+		self.days =
+			if self.size > 0 {
+				metadata.modified ().map (
+					Timestamp::from_system_time,
+				).unwrap_or_else (
+					|_| Timestamp::now (),
+				).days ()
+			} else {
+				Timestamp::now ().days ()
+			};
+
+		self.file = Some (file);
+
+		Ok (())
+
+	}
+
+	fn rotate (
+		& mut self,
+	) -> io::Result <()> {
+
+		self.file = None;
+
+		if self.keep > 0 {
+
+			for index in (1 .. self.keep).rev () {
+
+				let source =
+					self.rotated_path (
+						index);
+
+				if source.exists () {
+
+					fs::rename (
+						source,
+						self.rotated_path (
+							index + 1)) ?;
+
+				}
+
+			}
+
+			fs::rename (
+				& self.path,
+				self.rotated_path (1)) ?;
+
+		} else {
+
+			fs::remove_file (
+				& self.path) ?;
+
+		}
+
+		self.open ()
+
+	}
+
+	fn rotated_path (
+		& self,
+		index: usize,
+	) -> PathBuf {
+
+		let mut path =
+			self.path.clone ().into_os_string ();
+
+		path.push (
+			format! (".{}", index));
+
+		path.into ()
+
+	}
+
+	fn needs_rotation (
+		& self,
+		now: & Timestamp,
+		length: u64,
+	) -> bool {
+
+		if self.size == 0 {
+			return false;
+		}
+
+		match self.rotation {
+			FileRotation::Never => false,
+			FileRotation::Size (size) => self.size + length > size,
+			FileRotation::Daily => self.days != now.days (),
+		}
+
+	}
+
+	fn write (
+		& mut self,
+		now: & Timestamp,
+		buffer: & str,
+	) -> io::Result <()> {
+
+		if self.file.is_none () {
+			self.open () ?;
+		}
+
+		if self.needs_rotation (now, buffer.len () as u64) {
+			self.rotate () ?;
+		}
+
+		self.file.as_mut ().unwrap ().write_all (
+			buffer.as_bytes ()) ?;
+
+		self.size += buffer.len () as u64;
+
+		Ok (())
+
+	}
+
+}
+
+impl Backend for FileBackend {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		let now =
+			Timestamp::now ();
+
+		let mut buffer =
+			String::new ();
+
+		for log in logs {
+
+			match log.state () {
+
+				OutputLogState::Message => {
+
+					write! (
+						buffer,
+						"{} {}\n",
+						now,
+						log.message (),
+					).unwrap ();
+
+				},
+
+				OutputLogState::Complete | OutputLogState::Incomplete => {
+
+					write! (
+						buffer,
+						"{} {} ... {}\n",
+						now,
+						log.message (),
+						log.status_string (),
+					).unwrap ();
+
+					for line in log.kept_lines () {
+
+						write! (
+							buffer,
+							"{}     {}\n",
+							now,
+							line,
+						).unwrap ();
+
+					}
+
+				},
+
+				OutputLogState::Running | OutputLogState::Removed => (),
+
+			}
+
+		}
+
+		if buffer.is_empty () {
+			return;
+		}
+
+		self.write (
+			& now,
+			& buffer,
+		).unwrap_or_else (
+			|error| {
+
+				// try to reopen the file next time
+
+				self.file = None;
+
+				(self.error_handler) (
+					error)
+
+			}
+		);
+
+	}
+
+	fn synchronous (& self) -> bool {
+		false
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::env;
+	use std::path::Path;
+	use std::process;
+	use std::time::Duration;
+	use std::time::SystemTime;
+
+	use super::*;
+
+	/// Create an empty directory for a test's log files.
+	fn test_dir (
+		name: & str,
+	) -> PathBuf {
+
+		let path =
+			env::temp_dir ().join (
+				format! (
+					"output-file-test-{}-{}",
+					process::id (),
+					name));
+
+		fs::remove_dir_all (& path).ok ();
+
+		fs::create_dir (
+			& path,
+		).unwrap ();
+
+		path
+
+	}
+
+	/// Write five lines of 60 bytes, each of a different letter, so that a
+	/// limit of 100 bytes rotates before every line but the first.
+	fn write_lines (
+		file_backend: & mut FileBackend,
+	) {
+
+		let now =
+			Timestamp::now ();
+
+		for letter in "abcde".chars () {
+
+			let line =
+				format! (
+					"{}\n",
+					letter.to_string ().repeat (59));
+
+			file_backend.write (
+				& now,
+				& line,
+			).unwrap ();
+
+		}
+
+	}
+
+	fn file_letter (
+		path: & Path,
+	) -> Option <char> {
+
+		fs::read_to_string (
+			path,
+		).ok ().map (
+			|content| {
+
+				assert_eq! (content.len (), 60);
+
+				content.chars ().next ().unwrap ()
+
+			})
+
+	}
+
+	#[ test ]
+	fn size_rotation_keeps_two () {
+
+		let dir =
+			test_dir (
+				"keep-two");
+
+		let path =
+			dir.join ("test.log");
+
+		let mut file_backend =
+			FileBackend::new (
+				Box::new (|error| panic! ("{}", error)),
+				& path,
+				FileRotation::Size (100),
+				2,
+			).unwrap ();
+
+		write_lines (
+			& mut file_backend);
+
+		assert_eq! (file_letter (& path), Some ('e'));
+		assert_eq! (file_letter (& dir.join ("test.log.1")), Some ('d'));
+		assert_eq! (file_letter (& dir.join ("test.log.2")), Some ('c'));
+
+		assert_eq! (
+			fs::read_dir (& dir).unwrap ().count (),
+			3);
+
+		fs::remove_dir_all (& dir).ok ();
+
+	}
+
+	#[ test ]
+	fn size_rotation_keeps_none () {
+
+		let dir =
+			test_dir (
+				"keep-none");
+
+		let path =
+			dir.join ("test.log");
+
+		let mut file_backend =
+			FileBackend::new (
+				Box::new (|error| panic! ("{}", error)),
+				& path,
+				FileRotation::Size (100),
+				0,
+			).unwrap ();
+
+		write_lines (
+			& mut file_backend);
+
+		assert_eq! (file_letter (& path), Some ('e'));
+
+		assert_eq! (
+			fs::read_dir (& dir).unwrap ().count (),
+			1);
+
+		fs::remove_dir_all (& dir).ok ();
+
+	}
+
+	#[ test ]
+	fn needs_rotation () {
+
+		let dir =
+			test_dir (
+				"needs-rotation");
+
+		let mut file_backend =
+			FileBackend::new (
+				Box::new (|error| panic! ("{}", error)),
+				dir.join ("test.log"),
+				FileRotation::Daily,
+				1,
+			).unwrap ();
+
+		let now =
+			Timestamp::now ();
+
+		let tomorrow =
+			Timestamp::from_system_time (
+				SystemTime::now () + Duration::from_secs (86400));
+
+		// empty files are never rotated
+
+		assert! (! file_backend.needs_rotation (& tomorrow, 10));
+
+		file_backend.write (
+			& now,
+			"first\n",
+		).unwrap ();
+
+		assert! (! file_backend.needs_rotation (& now, 10));
+		assert! (file_backend.needs_rotation (& tomorrow, 10));
+
+		file_backend.rotation = FileRotation::Size (16);
+
+		assert! (! file_backend.needs_rotation (& now, 10));
+		assert! (file_backend.needs_rotation (& now, 11));
+
+		file_backend.rotation = FileRotation::Never;
+
+		assert! (! file_backend.needs_rotation (& tomorrow, 1000));
+
+		fs::remove_dir_all (& dir).ok ();
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt::Write as FormatWrite;
+use std::io;
+use std::io::Write;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use backend::*;
+use output_log::*;
+
+/// Backend for GitLab CI job logs.
+///
+/// Each job is written as a collapsible section when it finishes, so that
+/// the header can show its final status. Sections are written whole, so they
+/// never overlap however many jobs run at once, and messages are written as
+/// they arrive, outside of any section.
+pub struct GitlabOutput {
+	error_handler: Box <Fn (io::Error) + Send>,
+	start_times: HashMap <u64, u64>,
+}
+
+impl GitlabOutput {
+
+	pub fn new (
+		error_handler: Box <Fn (io::Error) + Send>,
+	) -> GitlabOutput {
+
+		GitlabOutput {
+			error_handler: error_handler,
+			start_times: HashMap::new (),
+		}
+
+	}
+
+	fn write_logs (
+		& mut self,
+		buffer: & mut String,
+		logs: & [OutputLogInternal],
+	) {
+
+		for log in logs {
+
+			match log.state () {
+
+				OutputLogState::Message => {
+
+					// this may replace a running job, which then has no section
+
+					self.start_times.remove (
+						& log.log_id ());
+
+					write! (
+						buffer,
+						"{}\n",
+						log.message (),
+					).unwrap ();
+
+				},
+
+				OutputLogState::Running => {
+
+					self.start_times.entry (
+						log.log_id (),
+					).or_insert_with (
+						unix_time);
+
+				},
+
+				OutputLogState::Complete | OutputLogState::Incomplete => {
+
+					let start_time =
+						self.start_times.remove (
+							& log.log_id (),
+						).unwrap_or_else (
+							unix_time);
+
+					let options =
+						if log.state () == OutputLogState::Complete {
+							"[collapsed=true]"
+						} else {
+							""
+						};
+
+					write! (
+						buffer,
+						"section_start:{}:output_job_{}{}\r\x1b[0K{} ... {}\n",
+						start_time,
+						log.log_id (),
+						options,
+						log.message (),
+						log.status_string (),
+					).unwrap ();
+
+					for line in log.kept_lines () {
+
+						write! (
+							buffer,
+							"    {}\n",
+							line,
+						).unwrap ();
+
+					}
+
+					write! (
+						buffer,
+						"section_end:{}:output_job_{}\r\x1b[0K\n",
+						unix_time (),
+						log.log_id (),
+					).unwrap ();
+
+				},
+
+				OutputLogState::Removed => {
+
+					self.start_times.remove (
+						& log.log_id ());
+
+				},
+
+			}
+
+		}
+
+	}
+
+}
+
+impl Backend for GitlabOutput {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		let mut buffer =
+			String::new ();
+
+		self.write_logs (
+			& mut buffer,
+			logs);
+
+		if buffer.is_empty () {
+			return;
+		}
+
+		write! (
+			io::stderr (),
+			"{}",
+			buffer,
+		).unwrap_or_else (
+			|error|
+
+			(self.error_handler) (
+				error)
+
+		);
+
+	}
+
+	fn synchronous (& self) -> bool {
+		true
+	}
+
+}
+
+fn unix_time (
+) -> u64 {
+
+	SystemTime::now ().duration_since (
+		UNIX_EPOCH,
+	).map (
+		|duration| duration.as_secs (),
+	).unwrap_or (0)
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::panic::Location;
+
+	use super::*;
+
+	fn log (
+		log_id: u64,
+		message: & str,
+		state: OutputLogState,
+	) -> OutputLogInternal {
+
+		OutputLogInternal::new (
+			log_id,
+			message.to_string (),
+			state,
+			OutputSeverity::Info,
+			Location::caller ())
+
+	}
+
+	/// Pass each set of logs to the backend in turn, as `OutputState` does,
+	/// and return what it writes with the section times left out.
+	fn write_updates (
+		updates: & [Vec <OutputLogInternal>],
+	) -> (GitlabOutput, String) {
+
+		let mut gitlab_output =
+			GitlabOutput::new (
+				Box::new (|error| panic! ("{}", error)));
+
+		let mut buffer =
+			String::new ();
+
+		for logs in updates {
+
+			gitlab_output.write_logs (
+				& mut buffer,
+				logs);
+
+		}
+
+		let buffer =
+			buffer.lines ().map (
+				|line|
+
+				match line.find (":output_job_") {
+
+					Some (index) if line.starts_with ("section_") =>
+						format! (
+							"{}:T{}\n",
+							& line [.. line.find (':').unwrap ()],
+							& line [index ..]),
+
+					_ => format! ("{}\n", line),
+
+				}
+
+			).collect ();
+
+		(gitlab_output, buffer)
+
+	}
+
+	#[ test ]
+	fn overlapping_jobs () {
+
+		let (gitlab_output, buffer) =
+			write_updates (& [
+				vec! [
+					log (1, "a", OutputLogState::Running),
+				],
+				vec! [
+					log (1, "a", OutputLogState::Running),
+					log (2, "b", OutputLogState::Running),
+					log (3, "message", OutputLogState::Message),
+				],
+				vec! [
+					log (1, "a", OutputLogState::Complete),
+					log (2, "b", OutputLogState::Running),
+				],
+				vec! [
+					log (2, "b", OutputLogState::Incomplete),
+				],
+			]);
+
+		// each section is whole, with its status in the header, and the
+		// message is outside of them
+
+		assert_eq! (
+			buffer,
+			concat! (
+				"message\n",
+				"section_start:T:output_job_1[collapsed=true]\r\x1b[0Ka ... done\n",
+				"section_end:T:output_job_1\r\x1b[0K\n",
+				"section_start:T:output_job_2\r\x1b[0Kb ... abort\n",
+				"section_end:T:output_job_2\r\x1b[0K\n",
+			));
+
+		assert! (gitlab_output.start_times.is_empty ());
+
+	}
+
+	#[ test ]
+	fn replaced_job () {
+
+		let (gitlab_output, buffer) =
+			write_updates (& [
+				vec! [
+					log (1, "c", OutputLogState::Running),
+				],
+				vec! [
+					log (1, "c replaced", OutputLogState::Message),
+				],
+				vec! [
+					log (2, "after", OutputLogState::Message),
+				],
+			]);
+
+		assert_eq! (buffer, "c replaced\nafter\n");
+		assert! (gitlab_output.start_times.is_empty ());
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
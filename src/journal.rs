@@ -0,0 +1,385 @@
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+
+use backend::*;
+use output_log::*;
+use system;
+
+pub const JOURNAL_SOCKET_PATH: & str = "/run/systemd/journal/socket";
+
+/// Backend which sends permanent messages and job outcomes to the systemd
+/// journal using its native datagram protocol.
+pub struct JournalBackend {
+	error_handler: Box <Fn (io::Error) + Send>,
+	socket: UnixDatagram,
+	path: PathBuf,
+	identifier: Option <String>,
+}
+
+impl JournalBackend {
+
+	pub fn new (
+		error_handler: Box <Fn (io::Error) + Send>,
+	) -> io::Result <JournalBackend> {
+
+		JournalBackend::new_with_path (
+			error_handler,
+			JOURNAL_SOCKET_PATH)
+
+	}
+
+	pub fn new_with_path <
+		SocketPath: Into <PathBuf>,
+	> (
+		error_handler: Box <Fn (io::Error) + Send>,
+		path: SocketPath,
+	) -> io::Result <JournalBackend> {
+
+		Ok (JournalBackend {
+			error_handler: error_handler,
+			socket: UnixDatagram::unbound () ?,
+			path: path.into (),
+			identifier: system::program_name (),
+		})
+
+	}
+
+	fn send (
+		& self,
+		log: & OutputLogInternal,
+	) -> io::Result <()> {
+
+		let (message, priority, state) =
+			match log.state () {
+
+			OutputLogState::Message => (
+				log.message ().to_string (),
+				log.severity ().syslog_level (),
+				"message",
+			),
+
+			OutputLogState::Complete => (
+				format! ("{} ... {}", log.message (), log.status_string ()),
+				log.status_severity ().syslog_level (),
+				"complete",
+			),
+
+			OutputLogState::Incomplete => (
+				format! ("{} ... {}", log.message (), log.status_string ()),
+				log.status_severity ().syslog_level (),
+				"incomplete",
+			),
+
+			OutputLogState::Running | OutputLogState::Removed =>
+				return Ok (()),
+
+		};
+
+		let mut datagram =
+			Vec::new ();
+
+		append_field (& mut datagram, "MESSAGE", & message);
+		append_field (& mut datagram, "PRIORITY", & priority.to_string ());
+		append_field (& mut datagram, "CODE_FILE", log.location ().file ());
+
+		append_field (
+			& mut datagram,
+			"CODE_LINE",
+			& log.location ().line ().to_string ());
+
+		if let Some (ref identifier) = self.identifier {
+			append_field (& mut datagram, "SYSLOG_IDENTIFIER", identifier);
+		}
+
+		append_field (
+			& mut datagram,
+			"OUTPUT_LOG_ID",
+			& log.log_id ().to_string ());
+
+		append_field (& mut datagram, "OUTPUT_LOG_STATE", state);
+
+		if ! log.kept_group_lines ().is_empty () {
+
+			append_field (
+				& mut datagram,
+				"OUTPUT_LOG_GROUP_LINES",
+				& log.kept_group_lines ().join ("\n"));
+
+		}
+
+		if ! log.failure ().is_empty () {
+
+			append_field (
+				& mut datagram,
+				"OUTPUT_LOG_FAILURE",
+				& log.failure ().join ("\n"));
+
+		}
+
+		self.socket.send_to (
+			& datagram,
+			& self.path) ?;
+
+		Ok (())
+
+	}
+
+}
+
+impl Backend for JournalBackend {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		for log in logs {
+
+			self.send (
+				log,
+			).unwrap_or_else (
+				|error|
+
+				(self.error_handler) (
+					error)
+
+			);
+
+		}
+
+	}
+
+	fn synchronous (& self) -> bool {
+		false
+	}
+
+}
+
+fn append_field (
+	datagram: & mut Vec <u8>,
+	name: & str,
+	value: & str,
+) {
+
+	datagram.extend_from_slice (
+		name.as_bytes ());
+
+	if value.contains ('\n') {
+
+		// values containing newlines use the length-prefixed binary form
+
+		datagram.push (b'\n');
+
+		datagram.extend_from_slice (
+			& (value.len () as u64).to_le_bytes ());
+
+	} else {
+
+		datagram.push (b'=');
+
+	}
+
+	datagram.extend_from_slice (
+		value.as_bytes ());
+
+	datagram.push (b'\n');
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::env;
+	use std::fs;
+	use std::os::unix::net::UnixDatagram;
+	use std::process;
+	use std::time::Duration;
+
+	use output::*;
+	use super::*;
+
+	fn receive_fields (
+		socket: & UnixDatagram,
+	) -> Vec <(String, Vec <u8>)> {
+
+		let mut datagram =
+			vec! [0; 65536];
+
+		let size =
+			socket.recv (
+				& mut datagram,
+			).unwrap ();
+
+		let mut datagram =
+			& datagram [.. size];
+
+		let mut fields =
+			Vec::new ();
+
+		while ! datagram.is_empty () {
+
+			let name_end =
+				datagram.iter ().position (
+					|& byte| byte == b'=' || byte == b'\n',
+				).unwrap ();
+
+			let name =
+				String::from_utf8 (
+					datagram [.. name_end].to_vec (),
+				).unwrap ();
+
+			let value =
+				if datagram [name_end] == b'=' {
+
+					let value_end =
+						name_end + 1 + datagram [name_end + 1 ..].iter ().position (
+							|& byte| byte == b'\n',
+						).unwrap ();
+
+					let value =
+						datagram [name_end + 1 .. value_end].to_vec ();
+
+					datagram =
+						& datagram [value_end + 1 ..];
+
+					value
+
+				} else {
+
+					let mut length =
+						[0; 8];
+
+					length.copy_from_slice (
+						& datagram [name_end + 1 .. name_end + 9]);
+
+					let value_start = name_end + 9;
+
+					let value_end =
+						value_start + u64::from_le_bytes (length) as usize;
+
+					assert_eq! (datagram [value_end], b'\n');
+
+					let value =
+						datagram [value_start .. value_end].to_vec ();
+
+					datagram =
+						& datagram [value_end + 1 ..];
+
+					value
+
+				};
+
+			fields.push (
+				(name, value));
+
+		}
+
+		fields
+
+	}
+
+	fn field <'a> (
+		fields: & 'a [(String, Vec <u8>)],
+		name: & str,
+	) -> Option <& 'a str> {
+
+		fields.iter ().find (
+			|& (field_name, _)| field_name == name,
+		).map (
+			|(_, value)| ::std::str::from_utf8 (value).unwrap (),
+		)
+
+	}
+
+	#[ test ]
+	fn journal_fields () {
+
+		let path =
+			env::temp_dir ().join (
+				format! (
+					"output-journal-test-{}.sock",
+					process::id ()));
+
+		fs::remove_file (& path).ok ();
+
+		let socket =
+			UnixDatagram::bind (
+				& path,
+			).unwrap ();
+
+		socket.set_read_timeout (
+			Some (Duration::from_secs (5)),
+		).unwrap ();
+
+		let backend =
+			JournalBackend::new_with_path (
+				Box::new (|error| panic! ("{}", error)),
+				& path,
+			).unwrap ();
+
+		let (message_line, job_line) = {
+
+			let output = Output::new (Some (Box::new (backend)));
+
+			let message_line = line! () + 1;
+			output.message ("first line\nsecond line");
+
+			let job_line = line! () + 1;
+			output.start_job ("building").fail ("exit status 1");
+
+			(message_line, job_line)
+
+		};
+
+		// the message uses the length-prefixed form for its newline
+
+		let fields =
+			receive_fields (
+				& socket);
+
+		assert_eq! (
+			field (& fields, "MESSAGE"),
+			Some ("first line\nsecond line"));
+
+		assert_eq! (field (& fields, "PRIORITY"), Some ("6"));
+		assert_eq! (field (& fields, "CODE_FILE"), Some (file! ()));
+
+		assert_eq! (
+			field (& fields, "CODE_LINE"),
+			Some (message_line.to_string ().as_str ()));
+
+		assert_eq! (field (& fields, "OUTPUT_LOG_STATE"), Some ("message"));
+		assert_eq! (field (& fields, "OUTPUT_LOG_FAILURE"), None);
+
+		let message_log_id =
+			field (& fields, "OUTPUT_LOG_ID").unwrap ().to_string ();
+
+		// the failed job is reported at error priority
+
+		let fields =
+			receive_fields (
+				& socket);
+
+		assert_eq! (field (& fields, "MESSAGE"), Some ("building ... abort"));
+		assert_eq! (field (& fields, "PRIORITY"), Some ("3"));
+		assert_eq! (field (& fields, "CODE_FILE"), Some (file! ()));
+
+		assert_eq! (
+			field (& fields, "CODE_LINE"),
+			Some (job_line.to_string ().as_str ()));
+
+		assert_eq! (field (& fields, "OUTPUT_LOG_STATE"), Some ("incomplete"));
+		assert_eq! (field (& fields, "OUTPUT_LOG_FAILURE"), Some ("exit status 1"));
+
+		assert_ne! (
+			field (& fields, "OUTPUT_LOG_ID"),
+			Some (message_log_id.as_str ()));
+
+		fs::remove_file (& path).ok ();
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
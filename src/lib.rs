@@ -32,23 +32,78 @@ extern crate lazy_static;
 extern crate libc;
 extern crate termion;
 
+#[ cfg (feature = "futures") ]
+extern crate futures_core;
+
+#[ cfg (feature = "rayon") ]
+extern crate rayon;
+
+#[ cfg (feature = "tokio") ]
+extern crate tokio;
+
 mod backend;
+mod command;
 mod console;
+mod counters;
+mod filebackend;
+mod gitlab;
+mod journal;
 mod output;
+#[ cfg (feature = "futures") ]
+mod output_futures;
+mod output_group;
+mod output_iter;
 mod output_log;
+#[ cfg (feature = "rayon") ]
+mod output_rayon;
 mod output_state;
+#[ cfg (feature = "tokio") ]
+mod output_tokio;
 mod pipe;
+mod progress_io;
 mod rawconsole;
+mod remote;
+mod render_loop;
+mod spinner;
+mod summary;
+mod syslog;
+mod system;
+mod teamcity;
+mod tee;
 mod ticksequence;
+mod timestamp;
+mod units;
 
+use std::env;
 use std::fs::File;
 
 pub use backend::*;
 pub use console::*;
+pub use counters::ProgressHandle;
+pub use filebackend::*;
+pub use gitlab::*;
+pub use journal::*;
 pub use output::*;
+#[ cfg (feature = "futures") ]
+pub use output_futures::*;
+pub use output_group::*;
+pub use output_iter::*;
 pub use output_log::*;
+#[ cfg (feature = "rayon") ]
+pub use output_rayon::*;
+#[ cfg (feature = "tokio") ]
+pub use output_tokio::*;
 pub use pipe::*;
+pub use progress_io::*;
 pub use rawconsole::*;
+pub use remote::*;
+pub use render_loop::*;
+pub use spinner::*;
+pub use summary::*;
+pub use syslog::*;
+pub use teamcity::*;
+pub use tee::*;
+pub use units::ByteUnits;
 
 pub fn open (
 ) -> Output {
@@ -116,7 +171,38 @@ pub fn open_backend (
 	let stderr_is_tty =
 		is_tty ("/dev/stderr");
 
-	if stdin_is_tty && stdout_is_tty && stderr_is_tty && raw {
+	if let Some (remote_socket) = env::var_os (REMOTE_SOCKET_VARIABLE) {
+
+		let remote_error_handler =
+			Box::new (
+				|_error| ()
+			);
+
+		if let Ok (remote_backend) =
+			RemoteBackend::new (
+				remote_error_handler,
+				remote_socket) {
+
+			return Box::new (
+				remote_backend);
+
+		}
+
+	}
+
+	if env::var_os ("GITLAB_CI").is_some () {
+
+		Box::new (
+			GitlabOutput::new (
+				error_handler))
+
+	} else if env::var_os ("TEAMCITY_VERSION").is_some () {
+
+		Box::new (
+			TeamcityOutput::new (
+				error_handler))
+
+	} else if stdin_is_tty && stdout_is_tty && stderr_is_tty && raw {
 
 		Box::new (
 			RawConsole::new (
@@ -1,4 +1,8 @@
+use std::error;
 use std::fmt;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::panic::Location;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Duration;
@@ -6,6 +10,7 @@ use std::time::Duration;
 use backend::*;
 use output_log::*;
 use output_state::*;
+use render_loop::*;
 
 #[ derive (Clone) ]
 pub struct Output {
@@ -13,6 +18,8 @@ pub struct Output {
 	prefix: String,
 	notice: bool,
 	debug: bool,
+	duration_threshold: Option <Duration>,
+	group: Option <u64>,
 }
 
 impl Output {
@@ -29,10 +36,41 @@ impl Output {
 			prefix: "".to_string (),
 			notice: true,
 			debug: false,
+			duration_threshold: None,
+			group: None,
 		}
 
 	}
 
+	/// Create an output without a background thread. Asynchronous backends
+	/// are only redrawn when the returned render loop is driven, which lets
+	/// the caller do so from an existing executor.
+	pub fn new_with_render_loop (
+		backend: Option <Box <Backend>>,
+	) -> (Output, OutputRenderLoop) {
+
+		let state =
+			OutputState::new_without_thread (
+				backend);
+
+		let render_loop =
+			OutputRenderLoop::new (
+				Arc::downgrade (& state),
+				Duration::from_millis (100));
+
+		let output = Output {
+			state: state,
+			prefix: "".to_string (),
+			notice: true,
+			debug: false,
+			duration_threshold: None,
+			group: None,
+		};
+
+		(output, render_loop)
+
+	}
+
 	#[ inline ]
 	pub fn new_with_options (
 		backend: Option <Box <Backend>>,
@@ -48,6 +86,8 @@ impl Output {
 			prefix: prefix,
 			notice: notice,
 			debug: debug,
+			duration_threshold: None,
+			group: None,
 		}
 
 	}
@@ -62,6 +102,8 @@ impl Output {
 			prefix: self.prefix.clone (),
 			notice: false,
 			debug: false,
+			duration_threshold: self.duration_threshold,
+			group: self.group,
 		}
 
 	}
@@ -76,6 +118,8 @@ impl Output {
 			prefix: self.prefix.clone (),
 			notice: true,
 			debug: false,
+			duration_threshold: self.duration_threshold,
+			group: self.group,
 		}
 
 	}
@@ -90,6 +134,8 @@ impl Output {
 			prefix: self.prefix.clone (),
 			notice: true,
 			debug: true,
+			duration_threshold: self.duration_threshold,
+			group: self.group,
 		}
 
 	}
@@ -108,26 +154,78 @@ impl Output {
 				prefix),
 			notice: true,
 			debug: true,
+			duration_threshold: self.duration_threshold,
+			group: self.group,
+		}
+
+	}
+
+	/// Clone this output, sending messages to the given group instead of
+	/// showing them directly.
+	#[ inline ]
+	pub (crate) fn in_group (
+		& self,
+		group_id: u64,
+	) -> Output {
+
+		Output {
+			group: Some (group_id),
+			.. self.clone ()
+		}
+
+	}
+
+	#[ inline ]
+	pub (crate) fn prefix_str (& self) -> & str {
+		& self.prefix
+	}
+
+	/// Show how long jobs took when they finish, for those which took at
+	/// least the given threshold.
+	#[ inline ]
+	pub fn show_durations (
+		& self,
+		threshold: Duration,
+	) -> Output {
+
+		Output {
+			duration_threshold: Some (threshold),
+			.. self.clone ()
 		}
 
 	}
 
 	#[ inline ]
+	pub fn hide_durations (
+		& self,
+	) -> Output {
+
+		Output {
+			duration_threshold: None,
+			.. self.clone ()
+		}
+
+	}
+
+	#[ inline ]
+	#[ track_caller ]
 	pub fn message_format (
 		& self,
 		arguments: fmt::Arguments,
 	) {
 
-		self.add_log (
+		self.add_log_severity (
 			format! (
 				"{}{}",
 				self.prefix,
 				arguments),
-			OutputLogState::Message);
+			OutputLogState::Message,
+			OutputSeverity::Info);
 
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn message <
 		Message: Into <String>,
 	> (
@@ -135,16 +233,18 @@ impl Output {
 		message: Message,
 	) {
 
-		self.add_log (
+		self.add_log_severity (
 			format! (
 				"{}{}",
 				self.prefix,
 				message.into ()),
-			OutputLogState::Message);
+			OutputLogState::Message,
+			OutputSeverity::Info);
 
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn debug_format (
 		& self,
 		arguments: fmt::Arguments,
@@ -152,18 +252,20 @@ impl Output {
 
 		if self.debug {
 
-			self.add_log (
+			self.add_log_severity (
 				format! (
 					"{}{}",
 					self.prefix,
 					arguments),
-				OutputLogState::Message);
+				OutputLogState::Message,
+				OutputSeverity::Debug);
 
 		}
 
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn notice <
 		Message: Into <String>,
 	> (
@@ -173,18 +275,20 @@ impl Output {
 
 		if self.notice {
 
-			self.add_log (
+			self.add_log_severity (
 				format! (
 					"{}{}",
 					self.prefix,
 					message.into ()),
-				OutputLogState::Message);
+				OutputLogState::Message,
+				OutputSeverity::Notice);
 
 		}
 
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn notice_format (
 		& self,
 		arguments: fmt::Arguments,
@@ -192,18 +296,20 @@ impl Output {
 
 		if self.notice {
 
-			self.add_log (
+			self.add_log_severity (
 				format! (
 					"{}{}",
 					self.prefix,
 					arguments),
-				OutputLogState::Message);
+				OutputLogState::Message,
+				OutputSeverity::Notice);
 
 		}
 
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn debug <
 		Message: Into <String>,
 	> (
@@ -213,18 +319,20 @@ impl Output {
 
 		if self.debug {
 
-			self.add_log (
+			self.add_log_severity (
 				format! (
 					"{}{}",
 					self.prefix,
 					message.into ()),
-				OutputLogState::Message);
+				OutputLogState::Message,
+				OutputSeverity::Debug);
 
 		}
 
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn start_job <
 		MessageString: Into <String>,
 	> (
@@ -232,12 +340,115 @@ impl Output {
 		message: MessageString,
 	) -> OutputLog {
 
-		self.add_log (
-			format! (
-				"{}{}",
-				self.prefix,
-				message.into ()),
-			OutputLogState::Running)
+		let output_log =
+			self.add_log_severity (
+				format! (
+					"{}{}",
+					self.prefix,
+					message.into ()),
+				OutputLogState::Running,
+				OutputSeverity::Info);
+
+		if self.duration_threshold.is_some () {
+
+			output_log.show_duration (
+				self.duration_threshold);
+
+		}
+
+		output_log
+
+	}
+
+	/// Run a closure as a job. The job completes if the closure returns
+	/// `Ok`, or is marked incomplete with the error and its causes if it
+	/// returns `Err`. If the closure panics, the job is marked incomplete
+	/// before the panic continues.
+	#[ track_caller ]
+	pub fn job <
+		MessageString: Into <String>,
+		Value,
+		Error: error::Error,
+		JobFn: FnOnce (& OutputLog) -> Result <Value, Error>,
+	> (
+		& self,
+		message: MessageString,
+		job_fn: JobFn,
+	) -> Result <Value, Error> {
+
+		let output_log =
+			self.start_job (
+				message);
+
+		match panic::catch_unwind (
+			AssertUnwindSafe (
+				|| job_fn (& output_log))) {
+
+			Ok (Ok (value)) => {
+
+				output_log.complete ();
+
+				Ok (value)
+
+			},
+
+			Ok (Err (error)) => {
+
+				output_log.incomplete_with (
+					& error);
+
+				Err (error)
+
+			},
+
+			Err (payload) => {
+
+				let reason =
+					if let Some (message) = payload.downcast_ref::<& str> () {
+						format! ("panicked: {}", message)
+					} else if let Some (message) = payload.downcast_ref::<String> () {
+						format! ("panicked: {}", message)
+					} else {
+						"panicked".to_string ()
+					};
+
+				output_log.fail (
+					reason);
+
+				panic::resume_unwind (
+					payload)
+
+			},
+
+		}
+
+	}
+
+	/// Show a header with progress across all jobs, such as "[12/40 done,
+	/// 3 failed] ETA 2m". This applies to every clone of this output.
+	#[ inline ]
+	pub fn show_summary (
+		& self,
+	) {
+
+		let mut self_state =
+			self.state.lock ().unwrap ();
+
+		self_state.set_summary_enabled (
+			true);
+
+	}
+
+	#[ inline ]
+	pub fn hide_summary (
+		& self,
+	) {
+
+		let mut self_state =
+			self.state.lock ().unwrap ();
+
+		self_state.set_summary_enabled (
+			false);
 
 	}
 
@@ -278,20 +489,58 @@ impl Output {
 	}
 
 	#[ inline ]
+	#[ track_caller ]
 	pub fn add_log (
 		& self,
 		message: String,
 		state: OutputLogState,
 	) -> OutputLog {
 
+		self.add_log_severity (
+			message,
+			state,
+			OutputSeverity::Info)
+
+	}
+
+	#[ inline ]
+	#[ track_caller ]
+	pub fn add_log_severity (
+		& self,
+		message: String,
+		state: OutputLogState,
+		severity: OutputSeverity,
+	) -> OutputLog {
+
+		let location =
+			Location::caller ();
+
 		let log_id = {
 
 			let mut self_state =
 				self.state.lock ().unwrap ();
 
+			// messages within a group are held by it, unless it has already
+			// finished
+
+			if let Some (group_id) = self.group {
+
+				if state == OutputLogState::Message
+					&& self_state.add_group_line (
+						group_id,
+						& message) {
+
+					return OutputLog::null ();
+
+				}
+
+			}
+
 			self_state.add_log (
 				message,
-				state)
+				state,
+				severity,
+				location)
 
 		};
 
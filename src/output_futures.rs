@@ -0,0 +1,213 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use futures_core::stream::Stream;
+
+use output::*;
+use output_log::*;
+
+// the output lock is never held by anything returned to the caller, so
+// these are safe to hold across await points and move between threads
+
+#[ allow (dead_code) ]
+fn assert_send_sync () {
+
+	fn check <Value: Send + Sync> () {}
+
+	check::<Output> ();
+	check::<OutputLog> ();
+
+}
+
+/// Extension trait which runs a future as a job.
+pub trait OutputFutureExt: Future + Sized {
+
+	/// Start a job which completes when the future does, or is marked
+	/// incomplete if the returned future is dropped before then.
+	#[ track_caller ]
+	fn with_output_job <
+		MessageString: Into <String>,
+	> (
+		self,
+		output: & Output,
+		message: MessageString,
+	) -> OutputJobFuture <Self> {
+
+		OutputJobFuture {
+			inner: self,
+			output_log: Some (
+				output.start_job (
+					message)),
+		}
+
+	}
+
+}
+
+impl <Inner: Future> OutputFutureExt for Inner {
+}
+
+pub struct OutputJobFuture <Inner> {
+	inner: Inner,
+	output_log: Option <OutputLog>,
+}
+
+impl <Inner: Future> Future for OutputJobFuture <Inner> {
+
+	type Output = Inner::Output;
+
+	fn poll (
+		self: Pin <& mut Self>,
+		context: & mut Context,
+	) -> Poll <Inner::Output> {
+
+		// safe because inner is never moved out of self
+
+		let this =
+			unsafe { self.get_unchecked_mut () };
+
+		let inner =
+			unsafe { Pin::new_unchecked (& mut this.inner) };
+
+		let poll =
+			inner.poll (
+				context);
+
+		if poll.is_ready () {
+
+			if let Some (output_log) = this.output_log.take () {
+				output_log.complete ();
+			}
+
+		}
+
+		poll
+
+	}
+
+}
+
+/// Extension trait which runs a stream as a job, advancing its progress
+/// as each item is produced.
+pub trait OutputStreamExt: Stream + Sized {
+
+	/// Start a job which completes when the stream ends, or is marked
+	/// incomplete if the returned stream is dropped before then.
+	#[ track_caller ]
+	fn with_output_job <
+		MessageString: Into <String>,
+	> (
+		self,
+		output: & Output,
+		message: MessageString,
+	) -> OutputJobStream <Self> {
+
+		let total =
+			match self.size_hint () {
+				(lower, Some (upper)) if lower == upper => lower as u64,
+				_ => 0,
+			};
+
+		let output_log =
+			output.start_job (
+				message);
+
+		if total > 0 {
+			output_log.progress (0, total);
+		} else {
+			output_log.count (0);
+		}
+
+		OutputJobStream {
+			inner: self,
+			output_log: Some (output_log),
+			position: 0,
+			total: total,
+		}
+
+	}
+
+}
+
+impl <Inner: Stream> OutputStreamExt for Inner {
+}
+
+pub struct OutputJobStream <Inner> {
+	inner: Inner,
+	output_log: Option <OutputLog>,
+	position: u64,
+	total: u64,
+}
+
+impl <Inner: Stream> Stream for OutputJobStream <Inner> {
+
+	type Item = Inner::Item;
+
+	fn poll_next (
+		self: Pin <& mut Self>,
+		context: & mut Context,
+	) -> Poll <Option <Inner::Item>> {
+
+		// safe because inner is never moved out of self
+
+		let this =
+			unsafe { self.get_unchecked_mut () };
+
+		let inner =
+			unsafe { Pin::new_unchecked (& mut this.inner) };
+
+		let poll =
+			inner.poll_next (
+				context);
+
+		match poll {
+
+			Poll::Ready (Some (_)) => {
+
+				this.position += 1;
+
+				if let Some (ref output_log) = this.output_log {
+
+					if this.total > 0 {
+
+						output_log.progress (
+							this.position,
+							this.total.max (this.position));
+
+					} else {
+
+						output_log.count (
+							this.position);
+
+					}
+
+				}
+
+			},
+
+			Poll::Ready (None) => {
+
+				if let Some (output_log) = this.output_log.take () {
+					output_log.complete ();
+				}
+
+			},
+
+			Poll::Pending => (),
+
+		}
+
+		poll
+
+	}
+
+	#[ inline ]
+	fn size_hint (& self) -> (usize, Option <usize>) {
+		self.inner.size_hint ()
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,86 @@
+use output::*;
+use output_log::*;
+
+/// A job which captures messages sent through its output. They are shown
+/// under it while it runs; if it completes they are hidden, otherwise they
+/// are kept along with its status.
+pub struct OutputGroup {
+	output_log: OutputLog,
+	output: Output,
+}
+
+impl Output {
+
+	#[ track_caller ]
+	pub fn group <
+		MessageString: Into <String>,
+	> (
+		& self,
+		message: MessageString,
+	) -> OutputGroup {
+
+		let output_log =
+			self.start_job (
+				message);
+
+		let output =
+			self.in_group (
+				output_log.log_id ());
+
+		OutputGroup {
+			output_log: output_log,
+			output: output,
+		}
+
+	}
+
+}
+
+impl OutputGroup {
+
+	/// Output whose messages are captured by this group.
+	#[ inline ]
+	pub fn output (& self) -> & Output {
+		& self.output
+	}
+
+	/// The job which represents this group, for progress and so on.
+	#[ inline ]
+	pub fn output_log (& self) -> & OutputLog {
+		& self.output_log
+	}
+
+	#[ inline ]
+	pub fn complete (
+		self,
+	) {
+
+		self.output_log.complete ();
+
+	}
+
+	#[ inline ]
+	pub fn incomplete (
+		self,
+	) {
+
+		self.output_log.incomplete ();
+
+	}
+
+	#[ inline ]
+	pub fn fail <
+		Reason: Into <String>,
+	> (
+		self,
+		reason: Reason,
+	) {
+
+		self.output_log.fail (
+			reason);
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
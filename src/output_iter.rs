@@ -0,0 +1,105 @@
+use output::*;
+use output_log::*;
+
+/// Extension trait which runs an iterator as a job, advancing its progress
+/// as each item is produced.
+pub trait OutputIteratorExt: Iterator + Sized {
+
+	/// Start a job which completes when the iterator is exhausted, or is
+	/// marked incomplete if the returned adaptor is dropped before then.
+	#[ track_caller ]
+	fn with_output_job <
+		MessageString: Into <String>,
+	> (
+		self,
+		output: & Output,
+		message: MessageString,
+	) -> OutputJobIter <Self> {
+
+		let total =
+			match self.size_hint () {
+				(lower, Some (upper)) if lower == upper => lower as u64,
+				_ => 0,
+			};
+
+		let output_log =
+			output.start_job (
+				message);
+
+		if total > 0 {
+			output_log.progress (0, total);
+		} else {
+			output_log.count (0);
+		}
+
+		OutputJobIter {
+			inner: self,
+			output_log: Some (output_log),
+			position: 0,
+			total: total,
+		}
+
+	}
+
+}
+
+impl <Inner: Iterator> OutputIteratorExt for Inner {
+}
+
+pub struct OutputJobIter <Inner> {
+	inner: Inner,
+	output_log: Option <OutputLog>,
+	position: u64,
+	total: u64,
+}
+
+impl <Inner: Iterator> Iterator for OutputJobIter <Inner> {
+
+	type Item = Inner::Item;
+
+	fn next (
+		& mut self,
+	) -> Option <Inner::Item> {
+
+		let item =
+			self.inner.next ();
+
+		if item.is_some () {
+
+			self.position += 1;
+
+			if let Some (ref output_log) = self.output_log {
+
+				if self.total > 0 {
+
+					output_log.progress (
+						self.position,
+						self.total.max (self.position));
+
+				} else {
+
+					output_log.count (
+						self.position);
+
+				}
+
+			}
+
+		} else if let Some (output_log) = self.output_log.take () {
+
+			output_log.complete ();
+
+		}
+
+		item
+
+	}
+
+	#[ inline ]
+	fn size_hint (& self) -> (usize, Option <usize>) {
+		self.inner.size_hint ()
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -1,7 +1,15 @@
+use std::error::Error;
+use std::panic::Location;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
 
+use counters::*;
 use output_state::*;
+use spinner::*;
+use units::*;
 
 pub struct OutputLog {
 	output_state: Option <Arc <Mutex <OutputState>>>,
@@ -19,12 +27,50 @@ pub enum OutputLogState {
 	Removed,
 }
 
+#[ derive (Clone, Copy, PartialEq, PartialOrd) ]
+pub enum OutputSeverity {
+	Debug,
+	Info,
+	Notice,
+	Warning,
+	Error,
+}
+
+/// How a job's final status should be presented.
+#[ derive (Clone, Copy, PartialEq) ]
+pub enum OutputStatusStyle {
+	Success,
+	Neutral,
+	Warning,
+	Failure,
+}
+
+/// Final status of a job, shown after its message, eg "done" or "skipped".
+/// Jobs finished with a failure style are considered incomplete.
+#[ derive (Clone) ]
+pub struct OutputStatus {
+	label: String,
+	style: OutputStatusStyle,
+}
+
 pub struct OutputLogInternal {
 	log_id: u64,
 	message: String,
-	numerator: u64,
+	detail: Option <String>,
+	severity: OutputSeverity,
+	location: & 'static Location <'static>,
+	counters: Arc <OutputLogCounters>,
+	sampled_counters: (u64, u64, u64),
 	denominator: u64,
-	tick: u64,
+	byte_units: Option <ByteUnits>,
+	count_unit: String,
+	spinner: Option <Spinner>,
+	started: Instant,
+	finished: Option <Instant>,
+	duration_threshold: Option <Duration>,
+	status: Option <OutputStatus>,
+	failure: Vec <String>,
+	group_lines: Vec <String>,
 	state: OutputLogState,
 }
 
@@ -52,6 +98,11 @@ impl OutputLog {
 
 	}
 
+	#[ inline ]
+	pub (crate) fn log_id (& self) -> u64 {
+		self.log_id
+	}
+
 	pub fn progress (
 		& self,
 		numerator: u64,
@@ -75,7 +126,10 @@ impl OutputLog {
 					panic! ();
 				}
 
-				log_internal.numerator = numerator;
+				log_internal.counters.numerator.store (
+					numerator,
+					Ordering::Relaxed);
+
 				log_internal.denominator = denominator;
 
 			}
@@ -86,6 +140,81 @@ impl OutputLog {
 
 	}
 
+	/// Report progress as a number of bytes, which will be displayed with
+	/// suitable units, along with the transfer rate.
+	pub fn progress_bytes (
+		& self,
+		numerator: u64,
+		denominator: u64,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			{
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.counters.numerator.store (
+					numerator,
+					Ordering::Relaxed);
+
+				log_internal.denominator = denominator;
+
+				if log_internal.byte_units.is_none () {
+					log_internal.byte_units = Some (ByteUnits::Binary);
+				}
+
+			}
+
+			output_state.update_backend_asynchronous ();
+
+		}
+
+	}
+
+	pub fn byte_units (
+		& self,
+		byte_units: ByteUnits,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			{
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.byte_units = Some (byte_units);
+
+			}
+
+			output_state.update_backend_asynchronous ();
+
+		}
+
+	}
+
 	pub fn tick (
 		& self,
 	) {
@@ -107,7 +236,198 @@ impl OutputLog {
 					panic! ();
 				}
 
-				log_internal.tick += 1;
+				log_internal.counters.tick.fetch_add (
+					1,
+					Ordering::Relaxed);
+
+
+			}
+
+			output_state.update_backend_asynchronous ();
+
+		}
+
+	}
+
+	/// Record the number of items processed so far, for open ended work
+	/// where the total is not known.
+	pub fn count (
+		& self,
+		count: u64,
+	) {
+
+		self.update_count (
+			|counters|
+			counters.count.store (
+				count,
+				Ordering::Relaxed));
+
+	}
+
+	pub fn count_add (
+		& self,
+		delta: u64,
+	) {
+
+		self.update_count (
+			|counters| {
+				counters.count.fetch_add (
+					delta,
+					Ordering::Relaxed);
+			});
+
+	}
+
+	pub fn count_unit <
+		Unit: Into <String>,
+	> (
+		& self,
+		unit: Unit,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			{
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.count_unit = unit.into ();
+
+			}
+
+			output_state.update_backend_asynchronous ();
+
+		}
+
+	}
+
+	fn update_count <
+		UpdateFn: FnOnce (& OutputLogCounters),
+	> (
+		& self,
+		update_fn: UpdateFn,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			{
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				update_fn (
+					& log_internal.counters);
+
+				log_internal.counters.counting.store (
+					true,
+					Ordering::Relaxed);
+
+			}
+
+			output_state.update_backend_asynchronous ();
+
+		}
+
+	}
+
+	/// Handle which can update this log's progress without locking.
+	pub fn progress_handle (
+		& self,
+	) -> ProgressHandle {
+
+		let counters =
+			self.output_state.as_ref ().map (
+				|output_state| {
+
+				let mut output_state =
+					output_state.lock ().unwrap ();
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				log_internal.counters.clone ()
+
+			}).unwrap_or_default ();
+
+		ProgressHandle::new (
+			counters)
+
+	}
+
+	/// Include the time taken when this job finishes, if it took at least
+	/// the given threshold, or stop doing so if the threshold is `None`.
+	pub fn show_duration (
+		& self,
+		threshold: Option <Duration>,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			let log_internal =
+				output_state.get_log_internal (
+					self.log_id,
+				).unwrap ();
+
+			if log_internal.state != OutputLogState::Running {
+				panic! ();
+			}
+
+			log_internal.duration_threshold = threshold;
+
+		}
+
+	}
+
+	pub fn spinner (
+		& self,
+		spinner: Spinner,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			{
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.spinner = Some (spinner);
 
 			}
 
@@ -169,7 +489,8 @@ impl OutputLog {
 					panic! ();
 				}
 
-				log_internal.state = OutputLogState::Complete;
+				log_internal.finish (
+					OutputLogState::Complete);
 
 				log_internal.state
 
@@ -203,7 +524,134 @@ impl OutputLog {
 					panic! ();
 				}
 
-				log_internal.state = OutputLogState::Incomplete;
+				log_internal.finish (
+					OutputLogState::Incomplete);
+
+				log_internal.state
+
+			};
+
+			output_state.update_backend_auto (
+				log_state);
+
+		}
+
+	}
+
+	/// Mark the job as incomplete, recording the reason, which will be shown
+	/// along with it.
+	pub fn fail <
+		Reason: Into <String>,
+	> (
+		self,
+		reason: Reason,
+	) {
+
+		self.fail_with_lines (
+			vec! [ reason.into () ]);
+
+	}
+
+	/// Mark the job as incomplete because of an error, recording the error
+	/// and its chain of causes.
+	pub fn incomplete_with (
+		self,
+		error: & Error,
+	) {
+
+		let mut lines =
+			vec! [ error.to_string () ];
+
+		let mut source =
+			error.source ();
+
+		while let Some (cause) = source {
+
+			lines.push (
+				format! (
+					"caused by: {}",
+					cause));
+
+			source = cause.source ();
+
+		}
+
+		self.fail_with_lines (
+			lines);
+
+	}
+
+	/// Mark the job as incomplete, recording several lines of explanation,
+	/// eg a reason followed by the relevant output of a failed command.
+	pub fn fail_with_lines (
+		self,
+		lines: Vec <String>,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			let log_state = {
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.finish (
+					OutputLogState::Incomplete);
+
+				log_internal.failure = lines;
+
+				log_internal.state
+
+			};
+
+			output_state.update_backend_auto (
+				log_state);
+
+		}
+
+	}
+
+	/// Finish the job with a custom status, eg `OutputStatus::skipped ()`.
+	pub fn finish_with (
+		self,
+		status: OutputStatus,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			let log_state = {
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.finish (
+					if status.style == OutputStatusStyle::Failure {
+						OutputLogState::Incomplete
+					} else {
+						OutputLogState::Complete
+					});
+
+				log_internal.status = Some (status);
 
 				log_internal.state
 
@@ -251,6 +699,62 @@ impl OutputLog {
 
 	}
 
+	/// Set a secondary line shown beneath a running job, eg the file being
+	/// worked on. Unlike `update`, this never forces an immediate redraw, so
+	/// it is cheap to call frequently.
+	pub fn detail <
+		Detail: Into <String>,
+	> (
+		& self,
+		detail: Detail,
+	) {
+
+		self.set_detail (
+			Some (detail.into ()));
+
+	}
+
+	pub fn clear_detail (
+		& self,
+	) {
+
+		self.set_detail (
+			None);
+
+	}
+
+	fn set_detail (
+		& self,
+		detail: Option <String>,
+	) {
+
+		if let Some (ref output_state) =
+			self.output_state {
+
+			let mut output_state =
+				output_state.lock ().unwrap ();
+
+			{
+
+				let log_internal =
+					output_state.get_log_internal (
+						self.log_id,
+					).unwrap ();
+
+				if log_internal.state != OutputLogState::Running {
+					panic! ();
+				}
+
+				log_internal.detail = detail;
+
+			}
+
+			output_state.mark_changed ();
+
+		}
+
+	}
+
 	pub fn replace (
 		self,
 		message: String,
@@ -311,7 +815,8 @@ impl Drop for OutputLog {
 					) {
 
 					if log_internal.state == OutputLogState::Running {
-						log_internal.state = OutputLogState::Incomplete;
+						log_internal.finish (
+							OutputLogState::Incomplete);
 					}
 
 					Some (log_internal.state)
@@ -337,6 +842,76 @@ impl Drop for OutputLog {
 
 }
 
+impl OutputStatus {
+
+	pub fn new <
+		Label: Into <String>,
+	> (
+		label: Label,
+		style: OutputStatusStyle,
+	) -> OutputStatus {
+
+		OutputStatus {
+			label: label.into (),
+			style: style,
+		}
+
+	}
+
+	pub fn done () -> OutputStatus {
+		OutputStatus::new ("done", OutputStatusStyle::Success)
+	}
+
+	pub fn abort () -> OutputStatus {
+		OutputStatus::new ("abort", OutputStatusStyle::Failure)
+	}
+
+	pub fn skipped () -> OutputStatus {
+		OutputStatus::new ("skipped", OutputStatusStyle::Neutral)
+	}
+
+	pub fn cached () -> OutputStatus {
+		OutputStatus::new ("cached", OutputStatusStyle::Neutral)
+	}
+
+	pub fn warning () -> OutputStatus {
+		OutputStatus::new ("warning", OutputStatusStyle::Warning)
+	}
+
+	pub fn failed () -> OutputStatus {
+		OutputStatus::new ("failed", OutputStatusStyle::Failure)
+	}
+
+	#[ inline ]
+	pub fn label (& self) -> & str {
+		& self.label
+	}
+
+	#[ inline ]
+	pub fn style (& self) -> OutputStatusStyle {
+		self.style
+	}
+
+}
+
+impl OutputSeverity {
+
+	/// Severity level as used by syslog and the systemd journal.
+	#[ inline ]
+	pub fn syslog_level (& self) -> u8 {
+
+		match * self {
+			OutputSeverity::Debug => 7,
+			OutputSeverity::Info => 6,
+			OutputSeverity::Notice => 5,
+			OutputSeverity::Warning => 4,
+			OutputSeverity::Error => 3,
+		}
+
+	}
+
+}
+
 impl OutputLogInternal {
 
 	#[ inline ]
@@ -344,14 +919,28 @@ impl OutputLogInternal {
 		log_id: u64,
 		message: String,
 		state: OutputLogState,
+		severity: OutputSeverity,
+		location: & 'static Location <'static>,
 	) -> OutputLogInternal {
 
 		OutputLogInternal {
 			log_id: log_id,
 			message: message.clone (),
-			numerator: 0,
+			detail: None,
+			severity: severity,
+			location: location,
+			counters: Arc::new (OutputLogCounters::default ()),
+			sampled_counters: (0, 0, 0),
 			denominator: 0,
-			tick: 0,
+			byte_units: None,
+			count_unit: String::new (),
+			spinner: None,
+			started: Instant::now (),
+			finished: None,
+			duration_threshold: None,
+			status: None,
+			failure: Vec::new (),
+			group_lines: Vec::new (),
 			state: state,
 		}
 
@@ -367,14 +956,29 @@ impl OutputLogInternal {
 		& self.message
 	}
 
+	#[ inline ]
+	pub fn detail (& self) -> Option <& str> {
+		self.detail.as_deref ()
+	}
+
 	#[ inline ]
 	pub fn state (& self) -> OutputLogState {
 		self.state
 	}
 
+	#[ inline ]
+	pub fn severity (& self) -> OutputSeverity {
+		self.severity
+	}
+
+	#[ inline ]
+	pub fn location (& self) -> & 'static Location <'static> {
+		self.location
+	}
+
 	#[ inline ]
 	pub fn numerator (& self) -> u64 {
-		self.numerator
+		self.counters.numerator.load (Ordering::Relaxed)
 	}
 
 	#[ inline ]
@@ -382,9 +986,283 @@ impl OutputLogInternal {
 		self.denominator
 	}
 
+	#[ inline ]
+	pub fn byte_units (& self) -> Option <ByteUnits> {
+		self.byte_units
+	}
+
 	#[ inline ]
 	pub fn tick (& self) -> u64 {
-		self.tick
+		self.counters.tick.load (Ordering::Relaxed)
+	}
+
+	#[ inline ]
+	pub fn count (& self) -> Option <u64> {
+
+		if self.counters.counting.load (Ordering::Relaxed) {
+			Some (self.counters.count.load (Ordering::Relaxed))
+		} else {
+			None
+		}
+
+	}
+
+	#[ inline ]
+	pub fn count_unit (& self) -> & str {
+		& self.count_unit
+	}
+
+	#[ inline ]
+	pub fn spinner (& self) -> Option <& Spinner> {
+		self.spinner.as_ref ()
+	}
+
+	#[ inline ]
+	pub fn started (& self) -> Instant {
+		self.started
+	}
+
+	#[ inline ]
+	pub fn finished (& self) -> Option <Instant> {
+		self.finished
+	}
+
+	/// Time taken by a finished log, or so far by a running one.
+	#[ inline ]
+	pub fn duration (& self) -> Duration {
+
+		self.finished.unwrap_or_else (
+			Instant::now,
+		).duration_since (
+			self.started)
+
+	}
+
+	/// Status shown after a finished job, eg "done" or "abort (3.1s)".
+	pub fn status_string (& self) -> String {
+
+		let mut status =
+			match (self.status.as_ref (), self.state) {
+				(Some (status), _) => status.label.clone (),
+				(None, OutputLogState::Incomplete) => "abort".to_string (),
+				(None, _) => "done".to_string (),
+			};
+
+		if let Some (duration_threshold) = self.duration_threshold {
+
+			let duration =
+				self.duration ();
+
+			if duration >= duration_threshold {
+
+				status.push_str (
+					& format! (
+						" ({})",
+						format_duration (duration)));
+
+			}
+
+		}
+
+		status
+
+	}
+
+	/// Final status of a finished job, which is "done" or "abort" unless a
+	/// custom status was given.
+	pub fn status (& self) -> OutputStatus {
+
+		match (self.status.as_ref (), self.state) {
+			(Some (status), _) => status.clone (),
+			(None, OutputLogState::Incomplete) => OutputStatus::abort (),
+			(None, _) => OutputStatus::done (),
+		}
+
+	}
+
+	/// Reason and causes recorded for a failed job, to be shown beneath it.
+	#[ inline ]
+	pub fn failure (& self) -> & [String] {
+		& self.failure
+	}
+
+	/// Messages captured by a group, shown while it runs, and kept only if
+	/// it does not complete.
+	#[ inline ]
+	pub fn group_lines (& self) -> & [String] {
+		& self.group_lines
+	}
+
+	#[ inline ]
+	pub fn add_group_line (
+		& mut self,
+		line: & str,
+	) {
+
+		self.group_lines.push (
+			line.to_string ());
+
+	}
+
+	/// Messages captured by a group which should still be shown now it has
+	/// finished, which is none unless it did not complete.
+	#[ inline ]
+	pub fn kept_group_lines (& self) -> & [String] {
+
+		if self.state == OutputLogState::Incomplete {
+			& self.group_lines
+		} else {
+			& []
+		}
+
+	}
+
+	/// Lines to show beneath a finished job: anything a group captured and
+	/// kept, followed by the reason for failure and its causes.
+	#[ inline ]
+	pub fn kept_lines (& self) -> impl Iterator <Item = & String> {
+
+		self.kept_group_lines ().iter ().chain (
+			self.failure.iter ())
+
+	}
+
+	pub fn status_style (& self) -> OutputStatusStyle {
+
+		match (self.status.as_ref (), self.state) {
+			(Some (status), _) => status.style,
+			(None, OutputLogState::Incomplete) => OutputStatusStyle::Failure,
+			(None, _) => OutputStatusStyle::Success,
+		}
+
+	}
+
+	/// Severity of a finished job, taking its status into account.
+	pub fn status_severity (& self) -> OutputSeverity {
+
+		match self.status_style () {
+			OutputStatusStyle::Warning => OutputSeverity::Warning,
+			OutputStatusStyle::Failure => OutputSeverity::Error,
+			_ => self.severity,
+		}
+
+	}
+
+	fn finish (
+		& mut self,
+		state: OutputLogState,
+	) {
+
+		self.state = state;
+		self.finished = Some (Instant::now ());
+
+	}
+
+	/// Textual progress for a running log, either a percentage, a number
+	/// of bytes, or a count of items along with the rate, if any of these
+	/// has been reported.
+	pub fn progress_string (& self) -> Option <String> {
+
+		let numerator =
+			self.numerator ();
+
+		if let Some (byte_units) = self.byte_units {
+
+			let mut progress =
+				format_bytes (
+					numerator,
+					byte_units);
+
+			if self.denominator > 0 {
+				progress.push_str (" / ");
+				progress.push_str (
+					& format_bytes (
+						self.denominator,
+						byte_units));
+			}
+
+			let elapsed =
+				self.started.elapsed ().as_secs_f64 ();
+
+			if elapsed >= 1.0 {
+
+				progress.push_str (
+					& format! (
+						" ({}/s)",
+						format_bytes (
+							(numerator as f64 / elapsed).round () as u64,
+							byte_units)));
+
+			}
+
+			Some (progress)
+
+		} else if let Some (percent) =
+			(numerator * 100).checked_div (self.denominator) {
+
+			Some (format! (
+				"{}%",
+				percent))
+
+		} else if let Some (count) = self.count () {
+
+			let mut progress =
+				format_count (count);
+
+			if ! self.count_unit.is_empty () {
+				progress.push (' ');
+				progress.push_str (& self.count_unit);
+			}
+
+			let elapsed =
+				self.started.elapsed ().as_secs_f64 ();
+
+			if elapsed >= 1.0 {
+
+				progress.push_str (
+					& format! (
+						" ({}/s)",
+						format_count (
+							(count as f64 / elapsed).round () as u64)));
+
+			}
+
+			Some (progress)
+
+		} else {
+
+			None
+
+		}
+
+	}
+
+	/// Check the shared counters for updates made without taking the lock,
+	/// returning true if they have changed since the last call.
+	pub fn sample_counters (& mut self) -> bool {
+
+		let counters = (
+			self.numerator (),
+			self.counters.count.load (Ordering::Relaxed),
+			self.tick (),
+		);
+
+		let changed =
+			counters != self.sampled_counters;
+
+		self.sampled_counters = counters;
+
+		changed
+
+	}
+
+	/// Current frame of this log's spinner, if it has one.
+	#[ inline ]
+	pub fn spinner_frame (& self) -> Option <& str> {
+
+		self.spinner.as_ref ().map (
+			|spinner| spinner.frame (self.started.elapsed ()))
+
 	}
 
 }
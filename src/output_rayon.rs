@@ -0,0 +1,98 @@
+use rayon::iter::ParallelIterator;
+use rayon::iter::plumbing::UnindexedConsumer;
+
+use output::*;
+use output_log::*;
+
+/// Extension trait which runs a parallel iterator as a job. Workers advance
+/// the job's progress through a shared atomic counter, which is picked up
+/// when the output is redrawn, so they never contend for the output lock.
+/// Synchronous backends are not redrawn regularly, so they only show this
+/// progress when something else updates them.
+pub trait OutputParallelIteratorExt: ParallelIterator {
+
+	/// Start a job which completes once the iterator has been driven to the
+	/// end. If that is interrupted by a panic the job is marked incomplete.
+	#[ track_caller ]
+	fn with_output_job <
+		MessageString: Into <String>,
+	> (
+		self,
+		output: & Output,
+		message: MessageString,
+	) -> OutputJobParIter <Self> {
+
+		let total =
+			self.opt_len ().unwrap_or (0) as u64;
+
+		let output_log =
+			output.start_job (
+				message);
+
+		if total > 0 {
+			output_log.progress (0, total);
+		} else {
+			output_log.count (0);
+		}
+
+		OutputJobParIter {
+			inner: self,
+			output_log: output_log,
+			total: total,
+		}
+
+	}
+
+}
+
+impl <Inner: ParallelIterator> OutputParallelIteratorExt for Inner {
+}
+
+pub struct OutputJobParIter <Inner> {
+	inner: Inner,
+	output_log: OutputLog,
+	total: u64,
+}
+
+impl <Inner: ParallelIterator> ParallelIterator for OutputJobParIter <Inner> {
+
+	type Item = Inner::Item;
+
+	fn drive_unindexed <Consumer> (
+		self,
+		consumer: Consumer,
+	) -> Consumer::Result
+	where Consumer: UnindexedConsumer <Self::Item> {
+
+		let OutputJobParIter { inner, output_log, total } = self;
+
+		let progress_handle =
+			output_log.progress_handle ();
+
+		let result =
+			inner.inspect (
+				move |_item| {
+
+				if total > 0 {
+					progress_handle.progress_add (1);
+				} else {
+					progress_handle.count_add (1);
+				}
+
+			}).drive_unindexed (
+				consumer);
+
+		output_log.complete ();
+
+		result
+
+	}
+
+	#[ inline ]
+	fn opt_len (& self) -> Option <usize> {
+		self.inner.opt_len ()
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::mem;
+use std::panic::Location;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::Weak;
@@ -8,6 +10,7 @@ use std::time::Duration;
 
 use backend::*;
 use output_log::*;
+use summary::*;
 
 pub struct OutputState {
 
@@ -15,8 +18,12 @@ pub struct OutputState {
 	synchronous: bool,
 
 	logs: Vec <OutputLogInternal>,
+	log_indexes: HashMap <u64, usize>,
 	next_log_id: u64,
 
+	summary: OutputSummary,
+	summary_enabled: bool,
+
 	background_join_handle: Option <thread::JoinHandle <()>>,
 	background_sender: Option <mpsc::Sender <()>>,
 
@@ -32,45 +39,23 @@ impl OutputState {
 		update_duration: Duration,
 	) -> Arc <Mutex <OutputState>> {
 
-		let synchronous =
-			backend.as_ref ().map (
-				|backend| backend.synchronous (),
-			).unwrap_or (true);
-
-		let real_self = OutputState {
-
-			backend: backend,
-			synchronous: synchronous,
-
-			logs: Vec::new (),
-			next_log_id: 0,
-
-			background_join_handle: None,
-			background_sender: None,
-
-			paused: false,
-			changed: false,
-
-		};
-
 		let shared_self =
-			Arc::new (Mutex::new (
-				real_self,
-			));
+			Self::new_without_thread (
+				backend);
 
-		if ! synchronous {
+		{
 
 			let mut real_self =
 				shared_self.lock ().expect (
 					"OutputState::new");
 
-			let (background_sender, background_receiver) =
-				mpsc::channel ();
+			if ! real_self.synchronous {
 
-			real_self.background_sender =
-				Some (background_sender);
+				let (background_sender, background_receiver) =
+					mpsc::channel ();
 
-			{
+				real_self.background_sender =
+					Some (background_sender);
 
 				let shared_self =
 					Arc::downgrade (
@@ -94,11 +79,51 @@ impl OutputState {
 
 	}
 
+	/// Create the state without a background thread, in which case
+	/// asynchronous backends are only updated when something else calls
+	/// `background_update`.
+	pub fn new_without_thread (
+		backend: Option <Box <Backend>>,
+	) -> Arc <Mutex <OutputState>> {
+
+		let synchronous =
+			backend.as_ref ().map (
+				|backend| backend.synchronous (),
+			).unwrap_or (true);
+
+		let real_self = OutputState {
+
+			backend: backend,
+			synchronous: synchronous,
+
+			logs: Vec::new (),
+			log_indexes: HashMap::new (),
+			next_log_id: 0,
+
+			summary: OutputSummary::new (),
+			summary_enabled: false,
+
+			background_join_handle: None,
+			background_sender: None,
+
+			paused: false,
+			changed: false,
+
+		};
+
+		Arc::new (Mutex::new (
+			real_self,
+		))
+
+	}
+
 	#[ inline ]
 	pub fn add_log (
 		& mut self,
 		message: String,
 		state: OutputLogState,
+		severity: OutputSeverity,
+		location: & 'static Location <'static>,
 	) -> u64 {
 
 		let log_id = self.next_log_id;
@@ -108,7 +133,13 @@ impl OutputState {
 			OutputLogInternal::new (
 				log_id,
 				message,
-				state);
+				state,
+				severity,
+				location);
+
+		self.log_indexes.insert (
+			log_id,
+			self.logs.len ());
 
 		self.logs.push (
 			log_internal);
@@ -120,15 +151,43 @@ impl OutputState {
 
 	}
 
+	/// Add a message to a running group, returning false if the group has
+	/// already finished.
+	pub fn add_group_line (
+		& mut self,
+		group_id: u64,
+		line: & str,
+	) -> bool {
+
+		match self.get_log_internal (group_id) {
+
+			Some (ref mut log_internal)
+				if log_internal.state () == OutputLogState::Running => {
+
+				log_internal.add_group_line (
+					line);
+
+			},
+
+			_ => return false,
+
+		}
+
+		self.update_backend_asynchronous ();
+
+		true
+
+	}
+
 	pub fn get_log_internal (
 		& mut self,
 		log_id: u64,
 	) -> Option <& mut OutputLogInternal> {
 
-		self.logs.iter_mut ().filter (
-			|log_internal|
-			log_internal.log_id () == log_id
-		).next ()
+		match self.log_indexes.get (& log_id) {
+			Some (& index) => Some (& mut self.logs [index]),
+			None => None,
+		}
 
 	}
 
@@ -163,6 +222,28 @@ impl OutputState {
 
 	}
 
+	/// Note that something has changed, to be picked up by the next update,
+	/// without updating the backend now even if it is synchronous.
+	pub fn mark_changed (
+		& mut self,
+	) {
+
+		self.changed = true;
+
+	}
+
+	pub fn set_summary_enabled (
+		& mut self,
+		summary_enabled: bool,
+	) {
+
+		self.summary_enabled =
+			summary_enabled;
+
+		self.update_backend_asynchronous ();
+
+	}
+
 	pub fn pause (
 		& mut self,
 	) {
@@ -194,6 +275,35 @@ impl OutputState {
 
 	}
 
+	fn sample_counters (
+		& mut self,
+	) -> bool {
+
+		let mut changed = false;
+
+		for log_internal in self.logs.iter_mut () {
+
+			if log_internal.sample_counters () {
+				changed = true;
+			}
+
+		}
+
+		changed
+
+	}
+
+	fn animating (
+		& self,
+	) -> bool {
+
+		self.logs.iter ().any (
+			|log_internal|
+			log_internal.state () == OutputLogState::Running
+			&& log_internal.spinner ().is_some ())
+
+	}
+
 	fn update_backend_synchronous (
 		& mut self,
 	) {
@@ -212,9 +322,24 @@ impl OutputState {
 			return;
 		}
 
+		// counters may have been updated without the lock, and synchronous
+		// backends have no background thread to sample them
+
+		self.sample_counters ();
+
+		self.summary.record (
+			& self.logs);
+
 		if let Some (ref mut backend) =
 			self.backend {
 
+			backend.summary (
+				if self.summary_enabled {
+					Some (& self.summary)
+				} else {
+					None
+				});
+
 			backend.update (
 				& self.logs);
 
@@ -225,16 +350,47 @@ impl OutputState {
 				& mut self.logs,
 				vec! []);
 
+		let old_len = logs_temp.len ();
+
 		self.logs =
 			logs_temp.into_iter ().filter (
 				|log_internal|
 				log_internal.state () == OutputLogState::Running
 			).collect ();
 
+		// logs stay in order, so indexes only move if some were removed
+
+		if self.logs.len () != old_len {
+
+			self.log_indexes =
+				self.logs.iter ().enumerate ().map (
+					|(index, log_internal)|
+					(log_internal.log_id (), index)
+				).collect ();
+
+		}
+
 		self.changed = false;
 
 	}
 
+	/// Periodic update of asynchronous backends, normally performed by the
+	/// background thread.
+	pub fn background_update (
+		& mut self,
+	) {
+
+		// spinners animate by time so need redrawing regardless, and
+		// counters may have been updated without the lock
+
+		if self.sample_counters () || self.animating () {
+			self.changed = true;
+		}
+
+		self.update_backend_real ();
+
+	}
+
 	fn background_thread (
 		shared_state: Weak <Mutex <OutputState>>,
 		background_receiver: mpsc::Receiver <()>,
@@ -265,7 +421,7 @@ impl OutputState {
 					shared_state.lock ().expect (
 						"OutputState::background_thread");
 
-				state.update_backend_real ();
+				state.background_update ();
 
 			}
 
@@ -283,20 +439,18 @@ impl Drop for OutputState {
 
 		// ask background thread to stop
 
-		let background_sender =
-			self.background_sender.take ().expect (
-				"OutputState::drop");
-
-		drop (background_sender);
+		drop (
+			self.background_sender.take ());
 
 		// wait for background thread to stop
 
-		let background_join_handle =
-			self.background_join_handle.take ().expect (
+		if let Some (background_join_handle) =
+			self.background_join_handle.take () {
+
+			background_join_handle.join ().expect (
 				"OutputState::drop");
 
-		background_join_handle.join ().expect (
-			"OutputState::drop");
+		}
 
 		// perform final update
 
@@ -0,0 +1,85 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use tokio::task::JoinHandle;
+use tokio::time::Interval;
+use tokio::time::MissedTickBehavior;
+
+use backend::*;
+use output::*;
+use render_loop::*;
+
+impl Output {
+
+	/// Create an output whose asynchronous backend is redrawn by a task on
+	/// the current tokio runtime, rather than by a dedicated thread. Must be
+	/// called from within a runtime.
+	pub fn new_tokio (
+		backend: Option <Box <Backend>>,
+	) -> Output {
+
+		let (output, render_loop) =
+			Output::new_with_render_loop (
+				backend);
+
+		spawn_render_loop (
+			render_loop);
+
+		output
+
+	}
+
+}
+
+/// Spawn a task on the current tokio runtime which drives a render loop
+/// until its output is dropped.
+pub fn spawn_render_loop (
+	render_loop: OutputRenderLoop,
+) -> JoinHandle <()> {
+
+	let mut interval =
+		tokio::time::interval (
+			render_loop.interval ());
+
+	interval.set_missed_tick_behavior (
+		MissedTickBehavior::Delay);
+
+	tokio::spawn (
+		RenderTask {
+			render_loop: render_loop,
+			interval: interval,
+		})
+
+}
+
+struct RenderTask {
+	render_loop: OutputRenderLoop,
+	interval: Interval,
+}
+
+impl Future for RenderTask {
+
+	type Output = ();
+
+	fn poll (
+		mut self: Pin <& mut Self>,
+		context: & mut Context,
+	) -> Poll <()> {
+
+		while self.interval.poll_tick (context).is_ready () {
+
+			if ! self.render_loop.render () {
+				return Poll::Ready (());
+			}
+
+		}
+
+		Poll::Pending
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
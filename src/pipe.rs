@@ -1,11 +1,22 @@
 use std::io;
 use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
 
 use backend::*;
 use output_log::*;
+use summary::*;
+
+/// How often `PipeOutput` reports the summary header, at most, if enabled.
+/// It has no background thread, so the summary is only reported when it is
+/// updated for some other reason, such as a message or a job finishing, and
+/// a long job which sends nothing in the meantime is not reported on.
+pub const PIPE_SUMMARY_INTERVAL: Duration = Duration::from_secs (10);
 
 pub struct PipeOutput {
 	error_handler: Box <Fn (io::Error) + Send>,
+	summary_time: Option <Instant>,
+	summary: String,
 }
 
 impl PipeOutput {
@@ -16,6 +27,8 @@ impl PipeOutput {
 
 		PipeOutput {
 			error_handler: error_handler,
+			summary_time: None,
+			summary: String::new (),
 		}
 
 	}
@@ -31,14 +44,47 @@ impl Backend for PipeOutput {
 
 		for log in logs {
 
-			if log.state () != OutputLogState::Message {
+			let mut buffer =
+				String::new ();
+
+			if log.state () == OutputLogState::Message {
+
+				buffer.push_str (
+					log.message ());
+
+				buffer.push ('\n');
+
+			} else if log.state () == OutputLogState::Incomplete
+				&& log.kept_lines ().next ().is_some () {
+
+				// jobs are not shown, but reasons for failure should be, as
+				// should anything a failed group captured
+
+				buffer.push_str (
+					& format! (
+						"{} ... {}\n",
+						log.message (),
+						log.status_string ()));
+
+				for line in log.kept_lines () {
+
+					buffer.push_str (
+						& format! (
+							"    {}\n",
+							line));
+
+				}
+
+			} else {
+
 				continue;
+
 			}
 
 			write! (
 				io::stderr (),
-				"{}\n",
-				log.message (),
+				"{}",
+				buffer,
 			).unwrap_or_else (
 				|error|
 
@@ -51,6 +97,51 @@ impl Backend for PipeOutput {
 
 	}
 
+	fn summary (
+		& mut self,
+		summary: Option <& OutputSummary>,
+	) {
+
+		let summary = match summary {
+			Some (summary) if summary.started () > 0 => summary,
+			_ => return,
+		};
+
+		// report on updates at least an interval apart, counting from the
+		// first job, and once all jobs have finished
+
+		let summary_time =
+			* self.summary_time.get_or_insert_with (
+				Instant::now);
+
+		let due =
+			summary.running () == 0
+			|| summary_time.elapsed () >= PIPE_SUMMARY_INTERVAL;
+
+		let summary =
+			summary.to_string ();
+
+		if ! due || summary == self.summary {
+			return;
+		}
+
+		write! (
+			io::stderr (),
+			"{}\n",
+			summary,
+		).unwrap_or_else (
+			|error|
+
+			(self.error_handler) (
+				error)
+
+		);
+
+		self.summary_time = Some (Instant::now ());
+		self.summary = summary;
+
+	}
+
 	fn synchronous (& self) -> bool {
 		true
 	}
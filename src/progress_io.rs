@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use output_log::*;
+
+/// Reader adaptor which reports the bytes read so far as progress on a job.
+pub struct ProgressRead <'a, Inner> {
+	output_log: & 'a OutputLog,
+	inner: Inner,
+	position: u64,
+	total: u64,
+}
+
+/// Writer adaptor which reports the bytes written so far as progress on a
+/// job.
+pub struct ProgressWrite <'a, Inner> {
+	output_log: & 'a OutputLog,
+	inner: Inner,
+	position: u64,
+	total: u64,
+}
+
+impl OutputLog {
+
+	pub fn wrap_read <Inner: Read> (
+		& self,
+		inner: Inner,
+	) -> ProgressRead <'_, Inner> {
+
+		self.wrap_read_sized (
+			inner,
+			0)
+
+	}
+
+	pub fn wrap_read_sized <Inner: Read> (
+		& self,
+		inner: Inner,
+		total: u64,
+	) -> ProgressRead <'_, Inner> {
+
+		self.progress_bytes (
+			0,
+			total);
+
+		ProgressRead {
+			output_log: self,
+			inner: inner,
+			position: 0,
+			total: total,
+		}
+
+	}
+
+	/// Wrap a file for reading, taking the total from its metadata.
+	pub fn wrap_read_file (
+		& self,
+		file: File,
+	) -> io::Result <ProgressRead <'_, File>> {
+
+		let total =
+			file.metadata () ?.len ();
+
+		Ok (self.wrap_read_sized (
+			file,
+			total))
+
+	}
+
+	pub fn wrap_write <Inner: Write> (
+		& self,
+		inner: Inner,
+	) -> ProgressWrite <'_, Inner> {
+
+		self.wrap_write_sized (
+			inner,
+			0)
+
+	}
+
+	pub fn wrap_write_sized <Inner: Write> (
+		& self,
+		inner: Inner,
+		total: u64,
+	) -> ProgressWrite <'_, Inner> {
+
+		self.progress_bytes (
+			0,
+			total);
+
+		ProgressWrite {
+			output_log: self,
+			inner: inner,
+			position: 0,
+			total: total,
+		}
+
+	}
+
+}
+
+impl <'a, Inner> ProgressRead <'a, Inner> {
+
+	#[ inline ]
+	pub fn position (& self) -> u64 {
+		self.position
+	}
+
+	#[ inline ]
+	pub fn get_ref (& self) -> & Inner {
+		& self.inner
+	}
+
+	#[ inline ]
+	pub fn get_mut (& mut self) -> & mut Inner {
+		& mut self.inner
+	}
+
+	#[ inline ]
+	pub fn into_inner (self) -> Inner {
+		self.inner
+	}
+
+}
+
+impl <'a, Inner: Read> Read for ProgressRead <'a, Inner> {
+
+	fn read (
+		& mut self,
+		buffer: & mut [u8],
+	) -> io::Result <usize> {
+
+		let length =
+			self.inner.read (
+				buffer) ?;
+
+		if length > 0 {
+
+			self.position += length as u64;
+
+			self.output_log.progress_bytes (
+				self.position,
+				self.total);
+
+		}
+
+		Ok (length)
+
+	}
+
+}
+
+impl <'a, Inner> ProgressWrite <'a, Inner> {
+
+	#[ inline ]
+	pub fn position (& self) -> u64 {
+		self.position
+	}
+
+	#[ inline ]
+	pub fn get_ref (& self) -> & Inner {
+		& self.inner
+	}
+
+	#[ inline ]
+	pub fn get_mut (& mut self) -> & mut Inner {
+		& mut self.inner
+	}
+
+	#[ inline ]
+	pub fn into_inner (self) -> Inner {
+		self.inner
+	}
+
+}
+
+impl <'a, Inner: Write> Write for ProgressWrite <'a, Inner> {
+
+	fn write (
+		& mut self,
+		buffer: & [u8],
+	) -> io::Result <usize> {
+
+		let length =
+			self.inner.write (
+				buffer) ?;
+
+		if length > 0 {
+
+			self.position += length as u64;
+
+			self.output_log.progress_bytes (
+				self.position,
+				self.total);
+
+		}
+
+		Ok (length)
+
+	}
+
+	fn flush (
+		& mut self,
+	) -> io::Result <()> {
+
+		self.inner.flush ()
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -14,7 +14,9 @@ use termion::raw::IntoRawMode;
 use termion::raw::RawTerminal;
 
 use backend::*;
+use console::*;
 use output_log::*;
+use summary::*;
 
 pub struct RawConsole <'a> {
 	error_handler: Box <Fn (io::Error) + Send>,
@@ -22,7 +24,9 @@ pub struct RawConsole <'a> {
 	_input_thread: JoinHandle <()>,
 	status_tick_sequence: & 'a [String],
 	columns: u16,
+	max_status_lines: u16,
 	status_lines: u16,
+	summary: Option <String>,
 }
 
 impl <'a> RawConsole <'a> {
@@ -45,13 +49,13 @@ impl <'a> RawConsole <'a> {
 
 		};
 
-		let columns =
+		let (columns, rows) =
 			match termion::terminal_size () {
 
-			Ok ((columns, _rows)) =>
-				columns,
+			Ok ((columns, rows)) =>
+				(columns, rows),
 
-			Err (_) => 80,
+			Err (_) => (80, 24),
 
 		};
 
@@ -69,7 +73,9 @@ impl <'a> RawConsole <'a> {
 				_output: output,
 				columns: columns,
 
+				max_status_lines: rows.saturating_sub (STATUS_LINES_MARGIN),
 				status_lines: 0,
+				summary: None,
 
 				_input_thread: input_thread,
 
@@ -80,6 +86,19 @@ impl <'a> RawConsole <'a> {
 
 	}
 
+	/// Limit the number of lines used to show running jobs. Jobs which do
+	/// not fit are summarised on the last line. Defaults to the terminal's
+	/// height, less `STATUS_LINES_MARGIN`.
+	pub fn set_max_status_lines (
+		& mut self,
+		max_status_lines: u16,
+	) {
+
+		self.max_status_lines =
+			max_status_lines;
+
+	}
+
 	fn input_thread (
 	) {
 
@@ -147,44 +166,6 @@ impl <'a> RawConsole <'a> {
 
 	}
 
-	fn write_running (
-		& self,
-		target: & mut FormatWrite,
-		message: & str,
-		status: Option <& str>,
-	) {
-
-		if let Some (status) = status {
-
-			write! (
-				target,
-				"{} ... {}{}\r\n",
-				if message.len () <= self.columns as usize - status.len () - 5 {
-					& message
-				} else {
-					& message [0 .. self.columns as usize - status.len () - 5]
-				},
-				status,
-				termion::clear::AfterCursor,
-			).unwrap ();
-
-		} else {
-
-			write! (
-				target,
-				"{} ...{}\r\n",
-				if message.len () <= self.columns as usize - 4 {
-					& message
-				} else {
-					& message [0 .. self.columns as usize - 4]
-				},
-				termion::clear::AfterCursor,
-			).unwrap ();
-
-		}
-
-	}
-
 }
 
 impl <'a> Backend for RawConsole <'a> {
@@ -210,85 +191,101 @@ impl <'a> Backend for RawConsole <'a> {
 
 		}
 
-		// output logs
+		// output permanent logs, which includes finished jobs
 
 		let old_status_lines = self.status_lines;
 		self.status_lines = 0;
 
 		for log in logs {
 
-			if log.state () == OutputLogState::Removed {
-				continue;
-			}
-
-			if log.state () == OutputLogState::Running
-				|| self.status_lines > 0 {
+			if log.state () == OutputLogState::Message {
 
-				self.status_lines += 1;
+				self.write_message (
+					& mut buffer,
+					log.message ());
 
-			}
+			} else if log.state () == OutputLogState::Complete {
 
-			if log.state () == OutputLogState::Running {
+				write_finished (
+					& mut buffer,
+					self.columns,
+					log);
 
-				if log.denominator () > 0 {
+			} else if log.state () == OutputLogState::Incomplete {
 
-					let percent_string =
-						format! (
-							"{}%",
-							log.numerator () * 100 / log.denominator ());
+				write_finished (
+					& mut buffer,
+					self.columns,
+					log);
 
-					self.write_running (
-						& mut buffer,
-						log.message (),
-						Some (& percent_string));
+				write_failure (
+					& mut buffer,
+					self.columns,
+					log);
 
-				} else if log.tick () > 0 {
+			}
 
-					let tick_string =
-						& self.status_tick_sequence [
-							(log.tick () as usize - 1)
-								% self.status_tick_sequence.len ()];
+		}
 
-					self.write_running (
-						& mut buffer,
-						log.message (),
-						Some (& tick_string));
+		// output the summary header, then running jobs, as many as will fit
 
-				} else {
+		let mut max_status_lines =
+			self.max_status_lines;
 
-					self.write_running (
-						& mut buffer,
-						log.message (),
-						None);
+		// the summary is only shown if there is room for it
 
-				}
+		if let Some (ref summary) = self.summary {
 
-			} else if log.state () == OutputLogState::Complete {
+			if max_status_lines > 0 {
 
-				self.write_running (
+				write_summary (
 					& mut buffer,
-					log.message (),
-					Some ("done"));
+					self.columns,
+					summary);
 
-			} else if log.state () == OutputLogState::Incomplete {
+				self.status_lines += 1;
 
-				self.write_running (
-					& mut buffer,
-					log.message (),
-					Some ("abort"));
+				max_status_lines -= 1;
 
-			} else if log.state () == OutputLogState::Message {
+			}
 
-				self.write_message (
-					& mut buffer,
-					log.message ());
+		}
 
-			} else {
+		let running: Vec <& OutputLogInternal> =
+			logs.iter ().filter (
+				|log| log.state () == OutputLogState::Running
+			).collect ();
 
-				unreachable! ();
+		let (log_lines, hidden) =
+			allocate_status_lines (
+				& running,
+				max_status_lines);
 
+		for (log, & lines) in running.iter ().zip (log_lines.iter ()) {
+
+			if lines == 0 {
+				continue;
 			}
 
+			write_status (
+				& mut buffer,
+				self.columns,
+				self.status_tick_sequence,
+				log,
+				lines);
+
+			self.status_lines += lines;
+
+		}
+
+		if hidden > 0 {
+
+			write_overflow (
+				& mut buffer,
+				hidden);
+
+			self.status_lines += 1;
+
 		}
 
 		if self.status_lines < old_status_lines {
@@ -326,6 +323,21 @@ impl <'a> Backend for RawConsole <'a> {
 
 	}
 
+	fn summary (
+		& mut self,
+		summary: Option <& OutputSummary>,
+	) {
+
+		// only shown while there are jobs running
+
+		self.summary =
+			summary.filter (
+				|summary| summary.running () > 0,
+			).map (
+				|summary| summary.to_string ());
+
+	}
+
 	fn synchronous (& self) -> bool {
 		false
 	}
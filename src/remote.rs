@@ -0,0 +1,789 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write as FormatWrite;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use backend::*;
+use output::*;
+use output_log::*;
+use units::*;
+
+/// Environment variable used to tell a child process where to send its
+/// output, if it uses this crate.
+pub const REMOTE_SOCKET_VARIABLE: & str = "OUTPUT_REMOTE_SOCKET";
+
+/// How long dropping a `RemoteListener` waits for connections to close.
+/// Child processes normally close theirs as they exit, but anything they
+/// started may have inherited the variable and still be connected.
+const REMOTE_DRAIN_TIMEOUT: Duration = Duration::from_secs (1);
+
+static NEXT_SOCKET_ID: AtomicUsize = AtomicUsize::new (0);
+
+/// Backend which forwards logs over a Unix socket to a parent process, which
+/// displays them as its own. Selected by `open` and similar when
+/// `OUTPUT_REMOTE_SOCKET` is set.
+pub struct RemoteBackend {
+	error_handler: Box <Fn (io::Error) + Send>,
+	stream: UnixStream,
+	sent: HashMap <u64, RemoteSent>,
+}
+
+struct RemoteSent {
+	update: String,
+	detail: Option <String>,
+}
+
+/// Listens for child processes using a `RemoteBackend`, and replays their
+/// logs on an `Output`. The child processes learn where to connect from
+/// `OUTPUT_REMOTE_SOCKET`, which `apply` sets.
+pub struct RemoteListener {
+	path: PathBuf,
+	stopping: Arc <AtomicBool>,
+	accept_thread: Option <JoinHandle <()>>,
+	connections: Arc <(Mutex <usize>, Condvar)>,
+}
+
+struct RemoteJob {
+	output_log: OutputLog,
+	message: String,
+	tick: u64,
+	failure: Vec <String>,
+}
+
+impl RemoteBackend {
+
+	pub fn new <
+		SocketPath: AsRef <Path>,
+	> (
+		error_handler: Box <Fn (io::Error) + Send>,
+		path: SocketPath,
+	) -> io::Result <RemoteBackend> {
+
+		Ok (RemoteBackend {
+			error_handler: error_handler,
+			stream: UnixStream::connect (path) ?,
+			sent: HashMap::new (),
+		})
+
+	}
+
+	fn write_update (
+		& mut self,
+		buffer: & mut String,
+		log: & OutputLogInternal,
+	) {
+
+		let update =
+			format! (
+				"update\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+				log.log_id (),
+				log.numerator (),
+				log.denominator (),
+				log.count ().map (
+					|count| count.to_string (),
+				).unwrap_or ("-".to_string ()),
+				log.tick (),
+				match log.byte_units () {
+					Some (ByteUnits::Binary) => "binary",
+					Some (ByteUnits::Si) => "si",
+					None => "-",
+				},
+				escape (log.message ()));
+
+		let detail =
+			log.detail ().map (
+				|detail| detail.to_string ());
+
+		let sent =
+			self.sent.entry (
+				log.log_id (),
+			).or_insert_with (
+				|| RemoteSent {
+					update: String::new (),
+					detail: None,
+				});
+
+		if sent.update != update {
+			buffer.push_str (& update);
+			sent.update = update;
+		}
+
+		if sent.detail != detail {
+
+			match detail {
+
+				Some (ref detail) => write! (
+					buffer,
+					"detail\t{}\t{}\n",
+					log.log_id (),
+					escape (detail),
+				).unwrap (),
+
+				None => write! (
+					buffer,
+					"clear_detail\t{}\n",
+					log.log_id (),
+				).unwrap (),
+
+			}
+
+			sent.detail = detail;
+
+		}
+
+	}
+
+}
+
+impl Backend for RemoteBackend {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		let mut buffer =
+			String::new ();
+
+		for log in logs {
+
+			match log.state () {
+
+				OutputLogState::Message => {
+
+					write! (
+						buffer,
+						"message\t{}\t{}\n",
+						log.log_id (),
+						escape (log.message ()),
+					).unwrap ();
+
+					self.sent.remove (
+						& log.log_id ());
+
+				},
+
+				OutputLogState::Running => {
+
+					self.write_update (
+						& mut buffer,
+						log);
+
+				},
+
+				OutputLogState::Complete | OutputLogState::Incomplete => {
+
+					self.write_update (
+						& mut buffer,
+						log);
+
+					// groups are not shown live by the parent, so anything they
+					// kept is sent along with the failure
+
+					for line in log.kept_lines () {
+
+						write! (
+							buffer,
+							"failure\t{}\t{}\n",
+							log.log_id (),
+							escape (line),
+						).unwrap ();
+
+					}
+
+					let status =
+						log.status ();
+
+					write! (
+						buffer,
+						"finish\t{}\t{}\t{}\n",
+						log.log_id (),
+						match status.style () {
+							OutputStatusStyle::Success => "success",
+							OutputStatusStyle::Neutral => "neutral",
+							OutputStatusStyle::Warning => "warning",
+							OutputStatusStyle::Failure => "failure",
+						},
+						escape (status.label ()),
+					).unwrap ();
+
+					self.sent.remove (
+						& log.log_id ());
+
+				},
+
+				OutputLogState::Removed => {
+
+					if self.sent.remove (& log.log_id ()).is_some () {
+
+						write! (
+							buffer,
+							"remove\t{}\n",
+							log.log_id (),
+						).unwrap ();
+
+					}
+
+				},
+
+			}
+
+		}
+
+		if buffer.is_empty () {
+			return;
+		}
+
+		self.stream.write_all (
+			buffer.as_bytes (),
+		).unwrap_or_else (
+			|error|
+
+			(self.error_handler) (
+				error)
+
+		);
+
+	}
+
+	fn synchronous (& self) -> bool {
+		false
+	}
+
+}
+
+impl RemoteListener {
+
+	pub fn new (
+		output: & Output,
+	) -> io::Result <RemoteListener> {
+
+		let path =
+			env::temp_dir ().join (
+				format! (
+					"output-{}-{}.sock",
+					process::id (),
+					NEXT_SOCKET_ID.fetch_add (1, Ordering::Relaxed)));
+
+		let listener =
+			UnixListener::bind (
+				& path) ?;
+
+		let stopping =
+			Arc::new (AtomicBool::new (false));
+
+		let connections =
+			Arc::new ((Mutex::new (0), Condvar::new ()));
+
+		let accept_thread = {
+
+			let output = output.clone ();
+			let stopping = stopping.clone ();
+			let connections = connections.clone ();
+
+			thread::spawn (move || {
+
+				for stream in listener.incoming () {
+
+					if stopping.load (Ordering::SeqCst) {
+						break;
+					}
+
+					let stream = match stream {
+						Ok (stream) => stream,
+						Err (_) => continue,
+					};
+
+					let output = output.clone ();
+
+					let connections = connections.clone ();
+
+					* connections.0.lock ().unwrap () += 1;
+
+					thread::spawn (move || {
+
+						Self::connection_thread (
+							output,
+							stream);
+
+						* connections.0.lock ().unwrap () -= 1;
+						connections.1.notify_all ();
+
+					});
+
+				}
+
+			})
+
+		};
+
+		Ok (RemoteListener {
+			path: path,
+			stopping: stopping,
+			accept_thread: Some (accept_thread),
+			connections: connections,
+		})
+
+	}
+
+	#[ inline ]
+	pub fn path (& self) -> & Path {
+		& self.path
+	}
+
+	/// Tell a command to send its output to this listener.
+	pub fn apply (
+		& self,
+		command: & mut Command,
+	) {
+
+		command.env (
+			REMOTE_SOCKET_VARIABLE,
+			& self.path);
+
+	}
+
+	fn connection_thread (
+		output: Output,
+		stream: UnixStream,
+	) {
+
+		let mut jobs: HashMap <u64, RemoteJob> =
+			HashMap::new ();
+
+		for line in BufReader::new (stream).lines () {
+
+			let line = match line {
+				Ok (line) => line,
+				Err (_) => break,
+			};
+
+			let fields: Vec <String> =
+				line.split ('\t').map (unescape).collect ();
+
+			let log_id =
+				match fields.get (1).and_then (
+					|log_id| log_id.parse::<u64> ().ok ()) {
+
+				Some (log_id) => log_id,
+				None => continue,
+
+			};
+
+			match (fields [0].as_str (), fields.len ()) {
+
+				("message", 3) => {
+
+					// this may replace a running job
+
+					if let Some (job) = jobs.remove (& log_id) {
+
+						job.output_log.replace (
+							format! (
+								"{}{}",
+								output.prefix_str (),
+								fields [2]));
+
+					} else {
+
+						output.message (
+							fields [2].as_str ());
+
+					}
+
+				},
+
+				("update", 8) => {
+
+					let message = & fields [7];
+
+					let job =
+						jobs.entry (log_id).or_insert_with (
+							|| RemoteJob {
+								output_log: output.start_job (
+									message.as_str ()),
+								message: message.clone (),
+								tick: 0,
+								failure: Vec::new (),
+							});
+
+					if & job.message != message {
+
+						job.output_log.update (
+							format! (
+								"{}{}",
+								output.prefix_str (),
+								message));
+
+						job.message = message.clone ();
+
+					}
+
+					let numerator =
+						fields [2].parse ().unwrap_or (0);
+
+					let denominator =
+						fields [3].parse ().unwrap_or (0);
+
+					match fields [6].as_str () {
+
+						"binary" | "si" => {
+
+							job.output_log.byte_units (
+								if fields [6] == "si" {
+									ByteUnits::Si
+								} else {
+									ByteUnits::Binary
+								});
+
+							job.output_log.progress_bytes (
+								numerator,
+								denominator);
+
+						},
+
+						_ => if denominator > 0 {
+
+							job.output_log.progress (
+								numerator,
+								denominator);
+
+						},
+
+					}
+
+					if let Ok (count) = fields [4].parse () {
+
+						job.output_log.count (
+							count);
+
+					}
+
+					let tick =
+						fields [5].parse ().unwrap_or (0);
+
+					if tick != job.tick {
+						job.output_log.tick ();
+						job.tick = tick;
+					}
+
+				},
+
+				("detail", 3) => {
+
+					if let Some (job) = jobs.get (& log_id) {
+						job.output_log.detail (
+							fields [2].as_str ());
+					}
+
+				},
+
+				("clear_detail", 2) => {
+
+					if let Some (job) = jobs.get (& log_id) {
+						job.output_log.clear_detail ();
+					}
+
+				},
+
+				("failure", 3) => {
+
+					if let Some (job) = jobs.get_mut (& log_id) {
+						job.failure.push (
+							fields [2].clone ());
+					}
+
+				},
+
+				("finish", 4) => {
+
+					if let Some (job) = jobs.remove (& log_id) {
+
+						let style = match fields [2].as_str () {
+							"success" => OutputStatusStyle::Success,
+							"neutral" => OutputStatusStyle::Neutral,
+							"warning" => OutputStatusStyle::Warning,
+							_ => OutputStatusStyle::Failure,
+						};
+
+						if job.failure.is_empty () {
+
+							job.output_log.finish_with (
+								OutputStatus::new (
+									fields [3].as_str (),
+									style));
+
+						} else {
+
+							job.output_log.fail_with_lines (
+								job.failure);
+
+						}
+
+					}
+
+				},
+
+				("remove", 2) => {
+
+					if let Some (job) = jobs.remove (& log_id) {
+						job.output_log.remove ();
+					}
+
+				},
+
+				_ => (),
+
+			}
+
+		}
+
+		// any jobs left running are dropped, which marks them incomplete
+
+	}
+
+}
+
+impl Drop for RemoteListener {
+
+	fn drop (
+		& mut self,
+	) {
+
+		// wake the accept thread so it sees that it should stop
+
+		self.stopping.store (
+			true,
+			Ordering::SeqCst);
+
+		drop (
+			UnixStream::connect (
+				& self.path));
+
+		if let Some (accept_thread) = self.accept_thread.take () {
+			accept_thread.join ().ok ();
+		}
+
+		// wait for connections to finish, so that everything sent by child
+		// processes has been shown, but not for ever
+
+		let (ref count, ref condvar) = * self.connections;
+
+		drop (
+			condvar.wait_timeout_while (
+				count.lock ().unwrap (),
+				REMOTE_DRAIN_TIMEOUT,
+				|count| * count > 0));
+
+		fs::remove_file (
+			& self.path,
+		).ok ();
+
+	}
+
+}
+
+fn escape (
+	value: & str,
+) -> String {
+
+	let mut escaped =
+		String::with_capacity (
+			value.len ());
+
+	for character in value.chars () {
+
+		match character {
+			'\\' => escaped.push_str ("\\\\"),
+			'\t' => escaped.push_str ("\\t"),
+			'\n' => escaped.push_str ("\\n"),
+			'\r' => escaped.push_str ("\\r"),
+			_ => escaped.push (character),
+		}
+
+	}
+
+	escaped
+
+}
+
+fn unescape (
+	value: & str,
+) -> String {
+
+	let mut unescaped =
+		String::with_capacity (
+			value.len ());
+
+	let mut characters =
+		value.chars ();
+
+	while let Some (character) = characters.next () {
+
+		if character != '\\' {
+			unescaped.push (character);
+			continue;
+		}
+
+		match characters.next () {
+			Some ('t') => unescaped.push ('\t'),
+			Some ('n') => unescaped.push ('\n'),
+			Some ('r') => unescaped.push ('\r'),
+			Some (other) => unescaped.push (other),
+			None => (),
+		}
+
+	}
+
+	unescaped
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::sync::Arc;
+	use std::sync::Mutex;
+
+	use super::*;
+
+	/// State, message and kept lines of a log which is no longer running.
+	type Record = (& 'static str, String, Vec <String>);
+
+	struct RecordingBackend {
+		records: Arc <Mutex <Vec <Record>>>,
+	}
+
+	impl Backend for RecordingBackend {
+
+		fn update (
+			& mut self,
+			logs: & [OutputLogInternal],
+		) {
+
+			let mut records =
+				self.records.lock ().unwrap ();
+
+			for log in logs {
+
+				let state = match log.state () {
+					OutputLogState::Running => continue,
+					OutputLogState::Message => "message",
+					OutputLogState::Complete => "complete",
+					OutputLogState::Incomplete => "incomplete",
+					OutputLogState::Removed => "removed",
+				};
+
+				records.push ((
+					state,
+					log.message ().to_string (),
+					log.kept_lines ().cloned ().collect (),
+				));
+
+			}
+
+		}
+
+		fn synchronous (& self) -> bool {
+			true
+		}
+
+	}
+
+	#[ test ]
+	fn round_trip () {
+
+		let records =
+			Arc::new (Mutex::new (Vec::new ()));
+
+		let parent =
+			Output::new (
+				Some (Box::new (RecordingBackend {
+					records: records.clone (),
+				})));
+
+		let listener =
+			RemoteListener::new (
+				& parent,
+			).unwrap ();
+
+		{
+
+			let child =
+				Output::new (
+					Some (Box::new (RemoteBackend::new (
+						Box::new (|error| panic! ("{}", error)),
+						listener.path (),
+					).unwrap ())));
+
+			child.message ("tab\there, newline\nthere, backslash \\ and \\t");
+
+			child.start_job (
+				"job\twith\ttabs",
+			).fail (
+				"first line\nsecond \\ line");
+
+			// make sure the parent sees the job running before it is replaced
+
+			let job =
+				child.start_job (
+					"replaced job");
+
+			child.flush ();
+
+			job.replace (
+				"replacement\tmessage".to_string ());
+
+		}
+
+		// waits for the connection to finish
+
+		drop (listener);
+
+		let records =
+			records.lock ().unwrap ();
+
+		assert_eq! (
+			* records,
+			vec! [
+				(
+					"message",
+					"tab\there, newline\nthere, backslash \\ and \\t".to_string (),
+					vec! [],
+				),
+				(
+					"incomplete",
+					"job\twith\ttabs".to_string (),
+					vec! [ "first line\nsecond \\ line".to_string () ],
+				),
+				(
+					"message",
+					"replacement\tmessage".to_string (),
+					vec! [],
+				),
+			]);
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Duration;
+
+use output_state::*;
+
+/// Drives updates of an asynchronous backend for an `Output` created with
+/// `Output::new_with_render_loop`, in place of the usual background thread.
+/// The owner should call `render` every `interval`, from a timer task or
+/// similar, until it returns false.
+pub struct OutputRenderLoop {
+	state: Weak <Mutex <OutputState>>,
+	interval: Duration,
+}
+
+impl OutputRenderLoop {
+
+	pub (crate) fn new (
+		state: Weak <Mutex <OutputState>>,
+		interval: Duration,
+	) -> OutputRenderLoop {
+
+		OutputRenderLoop {
+			state: state,
+			interval: interval,
+		}
+
+	}
+
+	#[ inline ]
+	pub fn interval (& self) -> Duration {
+		self.interval
+	}
+
+	/// Update the backend if anything has changed. Returns false once the
+	/// output has been dropped, after which this no longer needs calling.
+	pub fn render (
+		& self,
+	) -> bool {
+
+		if let Some (state) =
+			self.state.upgrade () {
+
+			let mut state =
+				state.lock ().expect (
+					"OutputRenderLoop::render");
+
+			state.background_update ();
+
+			true
+
+		} else {
+
+			false
+
+		}
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
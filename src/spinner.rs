@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+/// An animation shown next to a running job, advanced by wall clock time
+/// rather than by calls to `OutputLog::tick`.
+#[ derive (Clone) ]
+pub struct Spinner {
+	frames: Vec <String>,
+	interval: Duration,
+}
+
+impl Spinner {
+
+	pub fn new <
+		Frame: Into <String>,
+		Frames: IntoIterator <Item = Frame>,
+	> (
+		frames: Frames,
+		interval: Duration,
+	) -> Spinner {
+
+		let frames: Vec <String> =
+			frames.into_iter ().map (
+				|frame| frame.into (),
+			).collect ();
+
+		if frames.is_empty () {
+			panic! ("Spinner must have at least one frame");
+		}
+
+		if interval == Duration::from_secs (0) {
+			panic! ("Spinner interval must be greater than zero");
+		}
+
+		Spinner {
+			frames: frames,
+			interval: interval,
+		}
+
+	}
+
+	pub fn dots (
+	) -> Spinner {
+
+		Spinner::new (
+			vec! [ "   ", ".  ", ".. ", "...", " ..", "  ." ],
+			Duration::from_millis (200))
+
+	}
+
+	pub fn braille (
+	) -> Spinner {
+
+		Spinner::new (
+			vec! [ "⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏" ],
+			Duration::from_millis (100))
+
+	}
+
+	pub fn line (
+	) -> Spinner {
+
+		Spinner::new (
+			vec! [ "-", "\\", "|", "/" ],
+			Duration::from_millis (150))
+
+	}
+
+	pub fn bounce (
+	) -> Spinner {
+
+		Spinner::new (
+			vec! [
+				"[=    ]",
+				"[ =   ]",
+				"[  =  ]",
+				"[   = ]",
+				"[    =]",
+				"[   = ]",
+				"[  =  ]",
+				"[ =   ]",
+			],
+			Duration::from_millis (100))
+
+	}
+
+	#[ inline ]
+	pub fn frames (& self) -> & [String] {
+		& self.frames
+	}
+
+	#[ inline ]
+	pub fn interval (& self) -> Duration {
+		self.interval
+	}
+
+	pub fn frame (
+		& self,
+		elapsed: Duration,
+	) -> & str {
+
+		let index =
+			elapsed.as_millis () / self.interval.as_millis ().max (1);
+
+		& self.frames [
+			(index % self.frames.len () as u128) as usize]
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::time::Duration;
+use std::time::Instant;
+
+use output_log::*;
+
+/// Aggregate progress across every job started on an output, shown as a
+/// header such as "[12/40 done, 3 failed] ETA 2m".
+#[ derive (Clone) ]
+pub struct OutputSummary {
+	running: u64,
+	finished: u64,
+	failed: u64,
+	first_started: Option <Instant>,
+}
+
+impl OutputSummary {
+
+	pub fn new (
+	) -> OutputSummary {
+
+		OutputSummary {
+			running: 0,
+			finished: 0,
+			failed: 0,
+			first_started: None,
+		}
+
+	}
+
+	/// Count the jobs about to be passed to the backends. Finished jobs are
+	/// only passed once, and those still running every time, so these are
+	/// counted as they are rather than as they change. Jobs which are
+	/// removed or replaced with a message are forgotten.
+	pub (crate) fn record (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		self.running = 0;
+
+		for log in logs {
+
+			match log.state () {
+
+				OutputLogState::Running =>
+					self.running += 1,
+
+				OutputLogState::Complete =>
+					self.finished += 1,
+
+				OutputLogState::Incomplete => {
+					self.finished += 1;
+					self.failed += 1;
+				},
+
+				OutputLogState::Message | OutputLogState::Removed => (),
+
+			}
+
+		}
+
+		if self.running > 0 && self.first_started.is_none () {
+			self.first_started = Some (Instant::now ());
+		}
+
+	}
+
+	#[ inline ]
+	pub fn started (& self) -> u64 {
+		self.running + self.finished
+	}
+
+	#[ inline ]
+	pub fn finished (& self) -> u64 {
+		self.finished
+	}
+
+	#[ inline ]
+	pub fn failed (& self) -> u64 {
+		self.failed
+	}
+
+	#[ inline ]
+	pub fn running (& self) -> u64 {
+		self.running
+	}
+
+	/// Estimate the time until every job started so far has finished, from
+	/// the rate at which they have been finishing.
+	pub fn eta (& self) -> Option <Duration> {
+
+		let first_started =
+			self.first_started ?;
+
+		if self.finished == 0 || self.running == 0 {
+			return None;
+		}
+
+		let elapsed =
+			first_started.elapsed ();
+
+		Some (Duration::from_millis (
+			(elapsed.as_millis () * self.running as u128
+				/ self.finished as u128) as u64))
+
+	}
+
+}
+
+impl Default for OutputSummary {
+
+	fn default (
+	) -> OutputSummary {
+
+		OutputSummary::new ()
+
+	}
+
+}
+
+impl fmt::Display for OutputSummary {
+
+	fn fmt (
+		& self,
+		formatter: & mut fmt::Formatter,
+	) -> fmt::Result {
+
+		write! (
+			formatter,
+			"[{}/{} done",
+			self.finished,
+			self.started ()) ?;
+
+		if self.failed > 0 {
+
+			write! (
+				formatter,
+				", {} failed",
+				self.failed) ?;
+
+		}
+
+		write! (
+			formatter,
+			"]") ?;
+
+		if let Some (eta) = self.eta () {
+
+			write! (
+				formatter,
+				" ETA {}",
+				format_eta (eta)) ?;
+
+		}
+
+		Ok (())
+
+	}
+
+}
+
+/// Estimates are rough, so only show them to the nearest unit.
+fn format_eta (
+	eta: Duration,
+) -> String {
+
+	let seconds =
+		eta.as_secs () + if eta.subsec_millis () >= 500 { 1 } else { 0 };
+
+	if seconds < 60 {
+		format! ("{}s", seconds)
+	} else if seconds < 3600 {
+		format! ("{}m", (seconds + 30) / 60)
+	} else {
+		format! ("{}h{:02}m", seconds / 3600, seconds / 60 % 60)
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
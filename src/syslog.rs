@@ -0,0 +1,445 @@
+use std::io;
+use std::net::SocketAddr;
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::process;
+
+use backend::*;
+use output_log::*;
+use system;
+use timestamp::*;
+
+pub const SYSLOG_SOCKET_PATH: & str = "/dev/log";
+
+/// Where a `SyslogBackend` sends its records.
+#[ derive (Clone) ]
+pub enum SyslogTarget {
+	Unix (PathBuf),
+	Udp (SocketAddr),
+}
+
+#[ derive (Clone, Copy, PartialEq) ]
+pub enum SyslogFacility {
+	Kernel,
+	User,
+	Mail,
+	Daemon,
+	Auth,
+	Syslog,
+	Lpr,
+	News,
+	Uucp,
+	Cron,
+	AuthPriv,
+	Ftp,
+	Local0,
+	Local1,
+	Local2,
+	Local3,
+	Local4,
+	Local5,
+	Local6,
+	Local7,
+}
+
+enum SyslogSocket {
+	Unix (UnixDatagram, PathBuf),
+	Udp (UdpSocket, SocketAddr),
+}
+
+/// Backend which sends permanent messages and job outcomes as RFC 5424
+/// syslog records, over a local datagram socket or UDP.
+pub struct SyslogBackend {
+	error_handler: Box <Fn (io::Error) + Send>,
+	socket: SyslogSocket,
+	facility: SyslogFacility,
+	hostname: String,
+	app_name: String,
+	process_id: u32,
+}
+
+impl SyslogBackend {
+
+	pub fn new (
+		error_handler: Box <Fn (io::Error) + Send>,
+		target: SyslogTarget,
+		facility: SyslogFacility,
+	) -> io::Result <SyslogBackend> {
+
+		let socket = match target {
+
+			SyslogTarget::Unix (path) =>
+				SyslogSocket::Unix (
+					UnixDatagram::unbound () ?,
+					path),
+
+			SyslogTarget::Udp (address) =>
+				SyslogSocket::Udp (
+					UdpSocket::bind (
+						if address.is_ipv4 () {
+							"0.0.0.0:0"
+						} else {
+							"[::]:0"
+						}) ?,
+					address),
+
+		};
+
+		Ok (SyslogBackend {
+			error_handler: error_handler,
+			socket: socket,
+			facility: facility,
+			hostname: header_field (system::hostname (), 255),
+			app_name: header_field (system::program_name (), 48),
+			process_id: process::id (),
+		})
+
+	}
+
+	pub fn local (
+		error_handler: Box <Fn (io::Error) + Send>,
+		facility: SyslogFacility,
+	) -> io::Result <SyslogBackend> {
+
+		SyslogBackend::new (
+			error_handler,
+			SyslogTarget::Unix (SYSLOG_SOCKET_PATH.into ()),
+			facility)
+
+	}
+
+	fn send (
+		& self,
+		timestamp: & Timestamp,
+		log: & OutputLogInternal,
+	) -> io::Result <()> {
+
+		let (mut message, severity, message_id) =
+			match log.state () {
+
+			OutputLogState::Message => (
+				log.message ().to_string (),
+				log.severity (),
+				"message",
+			),
+
+			OutputLogState::Complete => (
+				format! ("{} ... {}", log.message (), log.status_string ()),
+				log.status_severity (),
+				"complete",
+			),
+
+			OutputLogState::Incomplete => (
+				format! ("{} ... {}", log.message (), log.status_string ()),
+				log.status_severity (),
+				"incomplete",
+			),
+
+			OutputLogState::Running | OutputLogState::Removed =>
+				return Ok (()),
+
+		};
+
+		let details: Vec <& str> =
+			log.kept_lines ().map (
+				String::as_str,
+			).collect ();
+
+		if ! details.is_empty () {
+			message.push_str (": ");
+			message.push_str (& details.join ("; "));
+		}
+
+		let record =
+			format! (
+				"<{}>1 {} {} {} {} {} - {}",
+				self.facility.code () as u32 * 8
+					+ severity.syslog_level () as u32,
+				timestamp,
+				self.hostname,
+				self.app_name,
+				self.process_id,
+				message_id,
+				message);
+
+		match self.socket {
+
+			SyslogSocket::Unix (ref socket, ref path) => {
+
+				socket.send_to (
+					record.as_bytes (),
+					path) ?;
+
+			},
+
+			SyslogSocket::Udp (ref socket, address) => {
+
+				socket.send_to (
+					record.as_bytes (),
+					address) ?;
+
+			},
+
+		}
+
+		Ok (())
+
+	}
+
+}
+
+impl Backend for SyslogBackend {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		let timestamp =
+			Timestamp::now ();
+
+		for log in logs {
+
+			self.send (
+				& timestamp,
+				log,
+			).unwrap_or_else (
+				|error|
+
+				(self.error_handler) (
+					error)
+
+			);
+
+		}
+
+	}
+
+	fn synchronous (& self) -> bool {
+		false
+	}
+
+}
+
+impl SyslogFacility {
+
+	#[ inline ]
+	pub fn code (& self) -> u8 {
+
+		match * self {
+			SyslogFacility::Kernel => 0,
+			SyslogFacility::User => 1,
+			SyslogFacility::Mail => 2,
+			SyslogFacility::Daemon => 3,
+			SyslogFacility::Auth => 4,
+			SyslogFacility::Syslog => 5,
+			SyslogFacility::Lpr => 6,
+			SyslogFacility::News => 7,
+			SyslogFacility::Uucp => 8,
+			SyslogFacility::Cron => 9,
+			SyslogFacility::AuthPriv => 10,
+			SyslogFacility::Ftp => 11,
+			SyslogFacility::Local0 => 16,
+			SyslogFacility::Local1 => 17,
+			SyslogFacility::Local2 => 18,
+			SyslogFacility::Local3 => 19,
+			SyslogFacility::Local4 => 20,
+			SyslogFacility::Local5 => 21,
+			SyslogFacility::Local6 => 22,
+			SyslogFacility::Local7 => 23,
+		}
+
+	}
+
+}
+
+/// Header fields must be printable ASCII without spaces, or "-" if unknown.
+fn header_field (
+	value: Option <String>,
+	max_length: usize,
+) -> String {
+
+	let value: String =
+		value.unwrap_or_default ().chars ().filter (
+			|character|
+			character.is_ascii_graphic ()
+		).take (max_length).collect ();
+
+	if value.is_empty () {
+		"-".to_string ()
+	} else {
+		value
+	}
+
+}
+
+#[ cfg (test) ]
+mod tests {
+
+	use std::env;
+	use std::fs;
+	use std::net::UdpSocket;
+	use std::os::unix::net::UnixDatagram;
+	use std::process;
+	use std::time::Duration;
+
+	use output::*;
+	use super::*;
+
+	/// Log a message, a notice, a job which completes and one which fails,
+	/// and check the records which are received for them.
+	fn check_records <
+		Receive: FnMut () -> String,
+	> (
+		backend: SyslogBackend,
+		mut receive: Receive,
+	) {
+
+		let header =
+			format! (
+				"{} {} {}",
+				backend.hostname,
+				backend.app_name,
+				backend.process_id);
+
+		{
+
+			let output =
+				Output::new (
+					Some (Box::new (backend)));
+
+			output.message ("plain message");
+			output.notice ("notice message");
+			output.start_job ("fetching").complete ();
+			output.start_job ("building").fail ("exit status 1");
+
+		}
+
+		// local3 is facility 19, so the priorities start at 19 * 8 = 152
+
+		let expected = [
+			(158, "message", "plain message"),
+			(157, "message", "notice message"),
+			(158, "complete", "fetching ... done"),
+			(155, "incomplete", "building ... abort: exit status 1"),
+		];
+
+		for & (priority, message_id, message) in expected.iter () {
+
+			let record =
+				receive ();
+
+			let fields: Vec <& str> =
+				record.splitn (7, ' ').collect ();
+
+			assert_eq! (fields.len (), 7, "{}", record);
+			assert_eq! (fields [0], format! ("<{}>1", priority));
+
+			// timestamps are RFC 3339 in UTC with milliseconds
+
+			let timestamp = fields [1];
+
+			assert_eq! (timestamp.len (), 24, "{}", record);
+			assert_eq! (& timestamp [10 .. 11], "T");
+			assert! (timestamp.ends_with ('Z'), "{}", record);
+
+			assert_eq! (fields [2 .. 5].join (" "), header);
+			assert_eq! (fields [5], message_id);
+			assert_eq! (fields [6], format! ("- {}", message));
+
+		}
+
+	}
+
+	#[ test ]
+	fn unix_records () {
+
+		let path =
+			env::temp_dir ().join (
+				format! (
+					"output-syslog-test-{}.sock",
+					process::id ()));
+
+		fs::remove_file (& path).ok ();
+
+		let socket =
+			UnixDatagram::bind (
+				& path,
+			).unwrap ();
+
+		socket.set_read_timeout (
+			Some (Duration::from_secs (5)),
+		).unwrap ();
+
+		let backend =
+			SyslogBackend::new (
+				Box::new (|error| panic! ("{}", error)),
+				SyslogTarget::Unix (path.clone ()),
+				SyslogFacility::Local3,
+			).unwrap ();
+
+		check_records (
+			backend,
+			|| {
+
+				let mut buffer =
+					vec! [0; 65536];
+
+				let size =
+					socket.recv (
+						& mut buffer,
+					).unwrap ();
+
+				String::from_utf8 (
+					buffer [.. size].to_vec (),
+				).unwrap ()
+
+			});
+
+		fs::remove_file (& path).ok ();
+
+	}
+
+	#[ test ]
+	fn udp_records () {
+
+		let socket =
+			UdpSocket::bind (
+				"127.0.0.1:0",
+			).unwrap ();
+
+		socket.set_read_timeout (
+			Some (Duration::from_secs (5)),
+		).unwrap ();
+
+		let backend =
+			SyslogBackend::new (
+				Box::new (|error| panic! ("{}", error)),
+				SyslogTarget::Udp (socket.local_addr ().unwrap ()),
+				SyslogFacility::Local3,
+			).unwrap ();
+
+		check_records (
+			backend,
+			|| {
+
+				let mut buffer =
+					vec! [0; 65536];
+
+				let size =
+					socket.recv (
+						& mut buffer,
+					).unwrap ();
+
+				String::from_utf8 (
+					buffer [.. size].to_vec (),
+				).unwrap ()
+
+			});
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
@@ -0,0 +1,51 @@
+use std::env;
+use std::path::Path;
+
+use libc;
+
+/// Base name of the running program, as used for syslog style identifiers.
+pub fn program_name (
+) -> Option <String> {
+
+	env::args_os ().next ().and_then (
+		|argument|
+		Path::new (& argument).file_name ().map (
+			|name| name.to_string_lossy ().into_owned ())
+	)
+
+}
+
+pub fn hostname (
+) -> Option <String> {
+
+	let mut buffer = [0u8; 256];
+
+	let result = unsafe {
+
+		libc::gethostname (
+			buffer.as_mut_ptr () as * mut libc::c_char,
+			buffer.len ())
+
+	};
+
+	if result != 0 {
+		return None;
+	}
+
+	let length =
+		buffer.iter ().position (
+			|byte| * byte == 0,
+		).unwrap_or (buffer.len ());
+
+	if length == 0 {
+		return None;
+	}
+
+	Some (
+		String::from_utf8_lossy (
+			& buffer [0 .. length],
+		).into_owned ())
+
+}
+
+// ex: noet ts=4 filetype=rust
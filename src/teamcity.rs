@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt::Write as FormatWrite;
+use std::io;
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use backend::*;
+use output_log::*;
+
+/// Backend which renders jobs as TeamCity service messages.
+///
+/// Jobs become blocks in the build log, updates to running jobs are sent
+/// as progress messages, and aborted jobs are reported as build problems.
+pub struct TeamcityOutput {
+	error_handler: Box <Fn (io::Error) + Send>,
+	blocks: HashMap <u64, TeamcityBlock>,
+}
+
+struct TeamcityBlock {
+	name: String,
+	progress: String,
+	progress_time: Option <Instant>,
+}
+
+impl TeamcityOutput {
+
+	pub fn new (
+		error_handler: Box <Fn (io::Error) + Send>,
+	) -> TeamcityOutput {
+
+		TeamcityOutput {
+			error_handler: error_handler,
+			blocks: HashMap::new (),
+		}
+
+	}
+
+}
+
+impl Backend for TeamcityOutput {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		let mut buffer =
+			String::new ();
+
+		for log in logs {
+
+			match log.state () {
+
+				OutputLogState::Message => {
+
+					write! (
+						buffer,
+						"{}\n",
+						log.message (),
+					).unwrap ();
+
+					// this may replace a running job, whose block is done
+
+					if let Some (block) =
+						self.blocks.remove (
+							& log.log_id ()) {
+
+						write! (
+							buffer,
+							"##teamcity[blockClosed name='{}']\n",
+							escape (& block.name),
+						).unwrap ();
+
+					}
+
+				},
+
+				OutputLogState::Running => {
+
+					let progress =
+						if let Some (progress_string) = log.progress_string () {
+							format! (
+								"{} {}",
+								log.message (),
+								progress_string)
+						} else {
+							log.message ().to_string ()
+						};
+
+					let block =
+						self.blocks.entry (
+							log.log_id (),
+						).or_insert_with (
+							|| {
+
+							write! (
+								buffer,
+								"##teamcity[blockOpened name='{}']\n",
+								escape (log.message ()),
+							).unwrap ();
+
+							TeamcityBlock {
+								name: log.message ().to_string (),
+								progress: String::new (),
+								progress_time: None,
+							}
+
+						});
+
+					// limit progress messages, since counts can change on
+					// every update
+
+					let progress_due =
+						block.progress_time.map (
+							|progress_time|
+							progress_time.elapsed () >= Duration::from_secs (1)
+						).unwrap_or (true);
+
+					if block.progress != progress && progress_due {
+
+						write! (
+							buffer,
+							"##teamcity[progressMessage '{}']\n",
+							escape (& progress),
+						).unwrap ();
+
+						block.progress = progress;
+						block.progress_time = Some (Instant::now ());
+
+					}
+
+				},
+
+				OutputLogState::Complete
+				| OutputLogState::Incomplete
+				| OutputLogState::Removed => {
+
+					if log.state () == OutputLogState::Incomplete {
+
+						// anything a group kept belongs in its block
+
+						for line in log.kept_group_lines () {
+
+							write! (
+								buffer,
+								"{}\n",
+								line,
+							).unwrap ();
+
+						}
+
+						write! (
+							buffer,
+							"##teamcity[buildProblem description='{}']\n",
+							escape (& format! (
+								"{} ... {}{}{}",
+								log.message (),
+								log.status_string (),
+								if log.failure ().is_empty () { "" } else { ": " },
+								log.failure ().join ("; "))),
+						).unwrap ();
+
+					}
+
+					if let Some (block) =
+						self.blocks.remove (
+							& log.log_id ()) {
+
+						write! (
+							buffer,
+							"##teamcity[blockClosed name='{}']\n",
+							escape (& block.name),
+						).unwrap ();
+
+					}
+
+				},
+
+			}
+
+		}
+
+		if buffer.is_empty () {
+			return;
+		}
+
+		write! (
+			io::stderr (),
+			"{}",
+			buffer,
+		).unwrap_or_else (
+			|error|
+
+			(self.error_handler) (
+				error)
+
+		);
+
+	}
+
+	fn synchronous (& self) -> bool {
+		true
+	}
+
+}
+
+fn escape (
+	value: & str,
+) -> String {
+
+	let mut escaped =
+		String::with_capacity (
+			value.len ());
+
+	for character in value.chars () {
+
+		match character {
+			'|' => escaped.push_str ("||"),
+			'\'' => escaped.push_str ("|'"),
+			'\n' => escaped.push_str ("|n"),
+			'\r' => escaped.push_str ("|r"),
+			'[' => escaped.push_str ("|["),
+			']' => escaped.push_str ("|]"),
+			_ => escaped.push (character),
+		}
+
+	}
+
+	escaped
+
+}
+
+// ex: noet ts=4 filetype=rust
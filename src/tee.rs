@@ -0,0 +1,64 @@
+use backend::*;
+use output_log::*;
+use summary::*;
+
+/// Backend which passes every update on to several other backends, for
+/// example a `Console` along with a `FileBackend`.
+pub struct TeeBackend {
+	backends: Vec <BoxBackend>,
+}
+
+impl TeeBackend {
+
+	pub fn new (
+		backends: Vec <BoxBackend>,
+	) -> TeeBackend {
+
+		TeeBackend {
+			backends: backends,
+		}
+
+	}
+
+}
+
+impl Backend for TeeBackend {
+
+	fn update (
+		& mut self,
+		logs: & [OutputLogInternal],
+	) {
+
+		for backend in self.backends.iter_mut () {
+
+			backend.update (
+				logs);
+
+		}
+
+	}
+
+	fn summary (
+		& mut self,
+		summary: Option <& OutputSummary>,
+	) {
+
+		for backend in self.backends.iter_mut () {
+
+			backend.summary (
+				summary);
+
+		}
+
+	}
+
+	fn synchronous (& self) -> bool {
+
+		self.backends.iter ().any (
+			|backend| backend.synchronous ())
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
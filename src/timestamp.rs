@@ -0,0 +1,112 @@
+use std::fmt;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// UTC calendar time, formatted as RFC 3339 with millisecond precision.
+#[ derive (Clone, Copy) ]
+pub struct Timestamp {
+	days: i64,
+	year: i64,
+	month: u32,
+	day: u32,
+	hour: u32,
+	minute: u32,
+	second: u32,
+	millisecond: u32,
+}
+
+impl Timestamp {
+
+	pub fn now (
+	) -> Timestamp {
+
+		Timestamp::from_system_time (
+			SystemTime::now ())
+
+	}
+
+	pub fn from_system_time (
+		time: SystemTime,
+	) -> Timestamp {
+
+		let duration =
+			time.duration_since (
+				UNIX_EPOCH,
+			).unwrap_or_default ();
+
+		let seconds =
+			duration.as_secs () as i64;
+
+		let days = seconds / 86400;
+		let seconds_of_day = (seconds % 86400) as u32;
+
+		// civil from days, see http://howardhinnant.github.io/date_algorithms.html
+
+		let shifted = days + 719468;
+		let era = shifted / 146097;
+		let day_of_era = shifted - era * 146097;
+
+		let year_of_era =
+			(day_of_era - day_of_era / 1460 + day_of_era / 36524
+				- day_of_era / 146096) / 365;
+
+		let day_of_year =
+			day_of_era - (365 * year_of_era + year_of_era / 4
+				- year_of_era / 100);
+
+		let month_index = (5 * day_of_year + 2) / 153;
+		let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+
+		let month =
+			if month_index < 10 {
+				month_index + 3
+			} else {
+				month_index - 9
+			} as u32;
+
+		let year =
+			year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+		Timestamp {
+			days: days,
+			year: year,
+			month: month,
+			day: day,
+			hour: seconds_of_day / 3600,
+			minute: seconds_of_day / 60 % 60,
+			second: seconds_of_day % 60,
+			millisecond: duration.subsec_millis (),
+		}
+
+	}
+
+	#[ inline ]
+	pub fn days (& self) -> i64 {
+		self.days
+	}
+
+}
+
+impl fmt::Display for Timestamp {
+
+	fn fmt (
+		& self,
+		formatter: & mut fmt::Formatter,
+	) -> fmt::Result {
+
+		write! (
+			formatter,
+			"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+			self.year,
+			self.month,
+			self.day,
+			self.hour,
+			self.minute,
+			self.second,
+			self.millisecond)
+
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust
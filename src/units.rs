@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+/// Style of unit prefixes used when formatting byte quantities.
+#[ derive (Clone, Copy, PartialEq) ]
+pub enum ByteUnits {
+
+	/// Powers of 1024, eg "KiB", "MiB"
+	Binary,
+
+	/// Powers of 1000, eg "kB", "MB"
+	Si,
+
+}
+
+/// Format an integer with commas separating groups of thousands.
+pub fn format_count (
+	value: u64,
+) -> String {
+
+	let digits =
+		value.to_string ();
+
+	let mut formatted =
+		String::with_capacity (
+			digits.len () + digits.len () / 3);
+
+	// digits left before the next separator, shorter for the first group
+
+	let mut group_remaining =
+		match digits.len () % 3 {
+			0 => 3,
+			length => length,
+		};
+
+	for digit in digits.chars () {
+
+		if group_remaining == 0 {
+			formatted.push (',');
+			group_remaining = 3;
+		}
+
+		formatted.push (digit);
+
+		group_remaining -= 1;
+
+	}
+
+	formatted
+
+}
+
+/// Format a duration briefly, eg "0.4s", "12.4s", "2m05s" or "1h02m".
+pub fn format_duration (
+	duration: Duration,
+) -> String {
+
+	let seconds =
+		duration.as_secs ();
+
+	if seconds < 60 {
+
+		format! (
+			"{}.{}s",
+			seconds,
+			duration.subsec_millis () / 100)
+
+	} else if seconds < 3600 {
+
+		format! (
+			"{}m{:02}s",
+			seconds / 60,
+			seconds % 60)
+
+	} else {
+
+		format! (
+			"{}h{:02}m",
+			seconds / 3600,
+			seconds / 60 % 60)
+
+	}
+
+}
+
+/// Format a number of bytes with a suitable unit, eg "312 MiB" or "1.4 GB".
+pub fn format_bytes (
+	value: u64,
+	units: ByteUnits,
+) -> String {
+
+	let (base, prefixes) = match units {
+		ByteUnits::Binary => (1024.0, [ "KiB", "MiB", "GiB", "TiB", "PiB", "EiB" ]),
+		ByteUnits::Si => (1000.0, [ "kB", "MB", "GB", "TB", "PB", "EB" ]),
+	};
+
+	if (value as f64) < base {
+		return format! ("{} B", value);
+	}
+
+	let mut scaled = value as f64 / base;
+	let mut prefix_index = 0;
+
+	while scaled >= base && prefix_index + 1 < prefixes.len () {
+		scaled /= base;
+		prefix_index += 1;
+	}
+
+	if scaled < 10.0 {
+		format! ("{:.1} {}", scaled, prefixes [prefix_index])
+	} else {
+		format! ("{:.0} {}", scaled, prefixes [prefix_index])
+	}
+
+}
+
+// ex: noet ts=4 filetype=rust